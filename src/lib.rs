@@ -1,4 +1,19 @@
-use std::fmt::Debug;
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
+use core::iter::{FromIterator, FusedIterator};
+use core::marker::PhantomData;
+use core::mem::{self, size_of, MaybeUninit};
+use core::ops::{Deref, DerefMut, Index, IndexMut};
 
 #[derive(Debug)]
 pub enum ErrDefine {
@@ -6,62 +21,360 @@ pub enum ErrDefine {
     ArrayIsFull = 2,
     ArrayIsEmpty = 3,
     ArraySizeError = 4,
+    Corrupted = 5,
 }
 
-#[derive(Default, Copy, Clone, Debug)]
-struct QuickElement<T: Sized + Default + Copy + Debug> {
-    pub data: T,
-    pub pre: u32,
-    pub next: u32,
-    pub cur: u32,
-    pub valid: bool,
+impl Display for ErrDefine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrDefine::InvalidIndex => write!(f, "invalid index"),
+            ErrDefine::ArrayIsFull => write!(f, "array is full"),
+            ErrDefine::ArrayIsEmpty => write!(f, "array is empty"),
+            ErrDefine::ArraySizeError => write!(f, "invalid array size"),
+            ErrDefine::Corrupted => write!(f, "array links are corrupted"),
+        }
+    }
 }
 
-#[derive(Debug)]
-pub struct QuickArray<T: Sized + Default + Copy + Debug> {
-    max_size: u32,
-    free_head: u32,
-    valid_head: u32,
-    valid_tail: u32,
-    valid_count: u32,
-    internal_vec: Vec<QuickElement<T>>,
+#[cfg(feature = "std")]
+impl std::error::Error for ErrDefine {}
+
+mod sealed {
+    pub trait Sealed {}
 }
 
-impl<T: Sized + Default + Copy + Debug> QuickArray<T> {
-    const INVALID_INDEX: u32 = 1994090994;
+/// Integer type used to store the links of every slot. A narrower type shrinks the
+/// per-element overhead, at the cost of a smaller maximum array size.
+pub trait IndexType: sealed::Sealed + Sized + Default + Copy + Debug + PartialEq {
+    /// Upper bound (exclusive) of `max_size` for arrays using this index type.
+    const MAX_SIZE: u32;
 
-    pub fn new(_max_size: u32) -> Self {
-        assert!(_max_size < Self::INVALID_INDEX, "Quick array is too large to init!");
-        if _max_size < 1 {
-            let _max_size = 1;
+    /// Reserved value marking a missing link; never a valid slot index.
+    #[doc(hidden)]
+    const NONE: Self;
+
+    #[doc(hidden)]
+    fn from_u32(index: u32) -> Self;
+
+    #[doc(hidden)]
+    fn to_u32(self) -> u32;
+}
+
+macro_rules! impl_index_type {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl IndexType for $t {
+                const MAX_SIZE: u32 = if (<$t>::MAX as u64) < (u32::MAX as u64) {
+                    <$t>::MAX as u32
+                } else {
+                    u32::MAX
+                };
+
+                const NONE: Self = <$t>::MAX;
+
+                #[inline]
+                fn from_u32(index: u32) -> Self {
+                    index as $t
+                }
+
+                #[inline]
+                fn to_u32(self) -> u32 {
+                    self as u32
+                }
+            }
+        )*
+    };
+}
+
+impl_index_type!(u8, u16, u32, usize);
+
+/// Fixed-size little-endian encoding of an element, used by `QuickArray::to_bytes` and
+/// `QuickArray::from_bytes`.
+pub trait ElementBytes: Sized {
+    const SIZE: usize;
+
+    fn write_bytes(&self, out: &mut Vec<u8>);
+
+    /// Decodes an element from exactly `SIZE` bytes.
+    fn read_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_element_bytes {
+    ($($t:ty),*) => {
+        $(
+            impl ElementBytes for $t {
+                const SIZE: usize = size_of::<$t>();
+
+                fn write_bytes(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_bytes(bytes: &[u8]) -> Self {
+                    let mut buf = [0u8; size_of::<$t>()];
+                    buf.copy_from_slice(bytes);
+                    <$t>::from_le_bytes(buf)
+                }
+            }
+        )*
+    };
+}
+
+impl_element_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+const SNAPSHOT_MAGIC: &[u8; 4] = b"QARR";
+const SNAPSHOT_VERSION: u32 = 1;
+const NO_LINK: u32 = u32::MAX;
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ErrDefine> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len()).ok_or(ErrDefine::Corrupted)?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, ErrDefine> {
+        Ok(u32::read_bytes(self.take(4)?))
+    }
+
+    fn link(&mut self) -> Result<Option<u32>, ErrDefine> {
+        let index = self.u32()?;
+        Ok(if index == NO_LINK { None } else { Some(index) })
+    }
+}
+
+/// An optional slot index stored in the compact index type, with `I::NONE` standing for
+/// "no link". `max_size < I::MAX_SIZE` keeps `I::NONE` out of the range of real slots.
+#[derive(Copy, Clone, PartialEq)]
+struct Link<I: IndexType>(I);
+
+impl<I: IndexType> Link<I> {
+    const NONE: Self = Link(I::NONE);
+
+    #[inline]
+    fn some(index: u32) -> Self {
+        Link(I::from_u32(index))
+    }
+
+    #[inline]
+    fn new(index: Option<u32>) -> Self {
+        match index {
+            Some(index) => Self::some(index),
+            None => Self::NONE,
+        }
+    }
+
+    #[inline]
+    fn get(self) -> Option<u32> {
+        if self == Self::NONE {
+            None
+        } else {
+            Some(self.0.to_u32())
+        }
+    }
+}
+
+impl<I: IndexType> Debug for Link<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+/// One slot of a `QuickArray`: the element and its list links. Only public so that storage
+/// types can name it; the fields stay private.
+#[derive(Copy, Clone)]
+pub struct QuickElement<T: Sized + Copy + Debug, I: IndexType> {
+    data: MaybeUninit<T>,
+    pre: Link<I>,
+    next: Link<I>,
+    cur: I,
+    valid: bool,
+    gen: u32,
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType> QuickElement<T, I> {
+    #[inline]
+    fn data(&self) -> &T {
+        debug_assert!(self.valid, "reading the data of a free slot");
+        // SAFETY: data is written whenever a slot becomes valid, and only valid slots are read
+        unsafe { self.data.assume_init_ref() }
+    }
+
+    #[inline]
+    fn data_mut(&mut self) -> &mut T {
+        debug_assert!(self.valid, "writing the data of a free slot");
+        // SAFETY: see `data`
+        unsafe { self.data.assume_init_mut() }
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType> Default for QuickElement<T, I> {
+    fn default() -> Self {
+        Self {
+            data: MaybeUninit::uninit(),
+            pre: Link::NONE,
+            next: Link::NONE,
+            cur: I::default(),
+            valid: false,
+            gen: 0,
+        }
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType> Debug for QuickElement<T, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("QuickElement");
+        if self.valid {
+            s.field("data", self.data());
         }
+        s.field("pre", &self.pre)
+            .field("next", &self.next)
+            .field("cur", &self.cur)
+            .field("valid", &self.valid)
+            .field("gen", &self.gen)
+            .finish()
+    }
+}
+
+/// Buffer holding the slots of a `QuickArray`. The array never changes the number of slots
+/// through this trait, it only reads and writes them through the slice, so any owner of a
+/// slot slice can back it; implement it for custom buffers such as a memory-mapped file.
+/// `Vec` is the default and the only storage the resizing methods are available for.
+///
+/// # Safety
+///
+/// `deref` and `deref_mut` must return the same slice, and its length must not change while
+/// the array owns the storage except through the `Vec` methods the array itself calls. The
+/// array checks indices against one and writes through raw pointers taken from the other.
+pub unsafe trait Storage<E>: DerefMut<Target = [E]> {}
+
+// SAFETY: both derefs return the vec's contents, which only the array resizes
+unsafe impl<E> Storage<E> for Vec<E> {}
+
+// SAFETY: a boxed slice has a fixed length and both derefs return it
+unsafe impl<E> Storage<E> for Box<[E]> {}
+
+// SAFETY: both derefs return the borrowed slice itself
+unsafe impl<E> Storage<E> for &mut [E] {}
+
+/// Inline buffer of `N` slots, used by `QuickArrayFixed`.
+#[derive(Debug, Clone)]
+pub struct FixedStorage<E, const N: usize>([E; N]);
+
+impl<E, const N: usize> Deref for FixedStorage<E, N> {
+    type Target = [E];
+
+    fn deref(&self) -> &[E] {
+        &self.0
+    }
+}
+
+impl<E, const N: usize> DerefMut for FixedStorage<E, N> {
+    fn deref_mut(&mut self) -> &mut [E] {
+        &mut self.0
+    }
+}
+
+// SAFETY: both derefs return the inline array
+unsafe impl<E, const N: usize> Storage<E> for FixedStorage<E, N> {}
+
+impl<E, const N: usize> FixedStorage<E, N> {
+    /// Evaluated when a `QuickArrayFixed` is built, turning `N == 0` into a compile error.
+    const NOT_EMPTY: () = assert!(N > 0, "QuickArrayFixed needs at least one slot");
+}
+
+/// A slot index paired with the generation of the element stored there. Once the slot is
+/// recycled the generation moves on, so a stale handle no longer resolves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Handle {
+    pub index: u32,
+    pub gen: u32,
+}
+
+/// One slot of a `RawSnapshot`: `(data, pre, next, cur, valid)`, with `data` set to `None`
+/// for free slots.
+pub type RawElement<T> = (Option<T>, Option<u32>, Option<u32>, u32, bool);
+
+/// Plain copy of the internal layout of a `QuickArray`, produced by `export_raw` and accepted
+/// by `import_raw`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawSnapshot<T> {
+    pub max_size: u32,
+    pub free_head: Option<u32>,
+    pub valid_head: Option<u32>,
+    pub valid_tail: Option<u32>,
+    pub valid_count: u32,
+    pub elements: Vec<RawElement<T>>,
+}
+
+/// `QuickArray` with room for exactly `N` elements stored inline, so it never allocates.
+/// Build one with `QuickArrayFixed::<T, N>::default()`; the resizing methods of the
+/// heap-backed array are not available. `N` must be at least 1, which is checked at compile
+/// time.
+pub type QuickArrayFixed<T, const N: usize, I = u32> = QuickArray<T, I, FixedStorage<QuickElement<T, I>, N>>;
+
+/// `QuickArray` whose slots live in a caller-provided buffer, such as a static, an arena or
+/// a DMA region; see `from_slice_storage`.
+pub type QuickArraySlice<'a, T, I = u32> = QuickArray<T, I, &'a mut [QuickElement<T, I>]>;
+
+pub struct QuickArray<T: Sized + Copy + Debug, I: IndexType = u32, S: Storage<QuickElement<T, I>> = Vec<QuickElement<T, I>>> {
+    max_size: u32,
+    free_head: Option<u32>,
+    valid_head: Option<u32>,
+    valid_tail: Option<u32>,
+    valid_count: u32,
+    /// Generation that slots grown back after a truncation start from, so handles to the
+    /// truncated slots never resolve again.
+    retired_gen: u32,
+    internal_vec: S,
+    marker: PhantomData<QuickElement<T, I>>,
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> QuickArray<T, I, S> {
+    /// Builds an empty array over `storage`, with one element per slot; whatever the slots
+    /// held before is discarded. Panics if the storage is empty or too large for the index
+    /// type.
+    pub fn with_storage(storage: S) -> Self {
+        assert!(!storage.is_empty(), "Quick array storage has no slots!");
+        assert!(storage.len() < I::MAX_SIZE as usize, "Quick array is too large to init!");
         let mut new_array = Self {
-            max_size: _max_size,
-            internal_vec: Vec::with_capacity(_max_size as usize),
-            free_head: 0,
-            valid_head: Self::INVALID_INDEX,
-            valid_tail: Self::INVALID_INDEX,
+            max_size: storage.len() as u32,
+            internal_vec: storage,
+            marker: PhantomData,
+            free_head: Some(0),
+            valid_head: None,
+            valid_tail: None,
             valid_count: 0,
+            retired_gen: 0,
         };
 
-        for _ in 0.._max_size {
-            new_array.internal_vec.push(QuickElement::<T>::default());
-        }
-
         new_array.init();
         new_array
     }
 
     pub fn clear(&mut self) {
-        self.free_head = 0;
-        self.valid_head = Self::INVALID_INDEX;
-        self.valid_tail = Self::INVALID_INDEX;
+        self.free_head = Some(0);
+        self.valid_head = None;
+        self.valid_tail = None;
         self.valid_count = 0;
 
         self.init();
     }
 
     #[inline]
+    pub fn len(&self) -> usize {
+        self.valid_count as usize
+    }
+
+    #[inline]
+    #[deprecated(note = "use `len`")]
     pub fn get_valid_count(&self) -> u32 {
         self.valid_count
     }
@@ -76,37 +389,107 @@ impl<T: Sized + Default + Copy + Debug> QuickArray<T> {
         self.valid_count == 0
     }
 
+    /// Maximum number of elements, i.e. `max_size`.
     #[inline]
+    pub fn capacity(&self) -> usize {
+        self.max_size as usize
+    }
+
+    #[inline]
+    #[deprecated(note = "use `capacity`")]
     pub fn get_max_size(&self) -> u32 {
         self.max_size
     }
 
+    #[inline]
+    pub fn available_capacity(&self) -> u32 {
+        self.max_size - self.valid_count
+    }
+
+    pub fn free_indices(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut index = self.free_head;
+        core::iter::from_fn(move || {
+            let cur_index = index?;
+            index = self.internal_vec[cur_index as usize].next.get();
+            Some(cur_index)
+        })
+    }
+
+    pub fn indices(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut index = self.valid_head;
+        core::iter::from_fn(move || {
+            let cur_index = index?;
+            index = self.internal_vec[cur_index as usize].next.get();
+            Some(cur_index)
+        })
+    }
+
+    /// Owned copy of the live slot indices in list order. Removals after the snapshot is taken
+    /// leave stale entries: `get_element` reports `None` for them, or a new value once the
+    /// slot is reused.
+    pub fn index_snapshot(&self) -> Vec<u32> {
+        let mut result = Vec::with_capacity(self.valid_count as usize);
+        result.extend(self.indices());
+        result
+    }
+
     pub fn get_head_element(&self) -> Option<&T> {
-        match self.valid_head {
-            Self::INVALID_INDEX => None,
-            _ => Some(&(self.internal_vec[self.valid_head as usize].data))
-        }
+        self.valid_head.map(|head| self.internal_vec[head as usize].data())
     }
 
     pub fn get_tail_element(&self) -> Option<&T> {
-        match self.valid_tail {
-            Self::INVALID_INDEX => None,
-            _ => Some(&(self.internal_vec[self.valid_tail as usize].data))
-        }
+        self.valid_tail.map(|tail| self.internal_vec[tail as usize].data())
+    }
+
+    #[inline]
+    pub fn first(&self) -> Option<&T> {
+        self.get_head_element()
+    }
+
+    #[inline]
+    pub fn last(&self) -> Option<&T> {
+        self.get_tail_element()
+    }
+
+    #[inline]
+    pub fn first_mut(&mut self) -> Option<&mut T> {
+        self.get_head_element_mut()
+    }
+
+    #[inline]
+    pub fn last_mut(&mut self) -> Option<&mut T> {
+        self.get_tail_element_mut()
+    }
+
+    pub fn get_head_element_mut(&mut self) -> Option<&mut T> {
+        let head = self.valid_head?;
+        Some(self.internal_vec[head as usize].data_mut())
+    }
+
+    pub fn get_tail_element_mut(&mut self) -> Option<&mut T> {
+        let tail = self.valid_tail?;
+        Some(self.internal_vec[tail as usize].data_mut())
     }
 
     pub fn get_head_index(&self) -> Option<u32> {
-        match self.valid_head {
-            Self::INVALID_INDEX => None,
-            _ => Some(self.internal_vec[self.valid_head as usize].cur)
-        }
+        self.valid_head
     }
 
     pub fn get_tail_index(&self) -> Option<u32> {
-        match self.valid_tail {
-            Self::INVALID_INDEX => None,
-            _ => Some(self.internal_vec[self.valid_tail as usize].cur)
-        }
+        self.valid_tail
+    }
+
+    pub fn peek_front(&self) -> Option<(u32, &T)> {
+        self.valid_head.map(|head| (head, self.internal_vec[head as usize].data()))
+    }
+
+    pub fn peek_back(&self) -> Option<(u32, &T)> {
+        self.valid_tail.map(|tail| (tail, self.internal_vec[tail as usize].data()))
+    }
+
+    #[inline]
+    pub fn is_valid_index(&self, index: u32) -> bool {
+        index < self.max_size && self.internal_vec[index as usize].valid
     }
 
     pub fn get_element(&self, index: u32) -> Option<&T> {
@@ -118,7 +501,20 @@ impl<T: Sized + Default + Copy + Debug> QuickArray<T> {
         if !e.valid {
             None
         } else {
-            Some(&e.data)
+            Some(e.data())
+        }
+    }
+
+    pub fn get_element_mut(&mut self, index: u32) -> Option<&mut T> {
+        if index >= self.max_size {
+            return None;
+        }
+
+        let e = &mut (self.internal_vec[index as usize]);
+        if !e.valid {
+            None
+        } else {
+            Some(e.data_mut())
         }
     }
 
@@ -128,10 +524,10 @@ impl<T: Sized + Default + Copy + Debug> QuickArray<T> {
         }
 
         let e = &(self.internal_vec[index as usize]);
-        if !e.valid || e.pre == Self::INVALID_INDEX {
+        if !e.valid {
             None
         } else {
-            Some(e.pre)
+            e.pre.get()
         }
     }
 
@@ -141,11 +537,129 @@ impl<T: Sized + Default + Copy + Debug> QuickArray<T> {
         }
 
         let e = &(self.internal_vec[index as usize]);
-        if !e.valid || e.next == Self::INVALID_INDEX {
+        if !e.valid {
+            None
+        } else {
+            e.next.get()
+        }
+    }
+
+    pub fn get_handle(&self, index: u32) -> Option<Handle> {
+        if !self.is_valid_index(index) {
+            None
+        } else {
+            Some(self.make_handle(index))
+        }
+    }
+
+    pub fn get_by_handle(&self, handle: Handle) -> Option<&T> {
+        if handle.index >= self.max_size {
+            return None;
+        }
+
+        let e = &(self.internal_vec[handle.index as usize]);
+        if !e.valid || e.gen != handle.gen {
             None
         } else {
-            Some(e.next)
+            Some(e.data())
+        }
+    }
+
+    pub fn find<F: Fn(&T) -> bool>(&self, pred: F) -> Option<u32> {
+        let mut index = self.valid_head;
+        while let Some(cur_index) = index {
+            let e = &self.internal_vec[cur_index as usize];
+            if pred(e.data()) {
+                return Some(cur_index);
+            }
+            index = e.next.get();
+        }
+
+        None
+    }
+
+    pub fn find_map<U, F: FnMut(&T) -> Option<U>>(&self, mut f: F) -> Option<U> {
+        let mut index = self.valid_head;
+        while let Some(cur_index) = index {
+            let e = &self.internal_vec[cur_index as usize];
+            if let Some(result) = f(e.data()) {
+                return Some(result);
+            }
+            index = e.next.get();
+        }
+
+        None
+    }
+
+    /// Builds a lookup from `key` to the current elements. When several elements share a key
+    /// the first in list order wins.
+    #[cfg(feature = "std")]
+    pub fn rebuild_lookup<K: Eq + Hash, F: Fn(&T) -> K>(&self, key: F) -> KeyLookup<K> {
+        let mut map = std::collections::HashMap::with_capacity(self.valid_count as usize);
+        for (index, data) in self.iter() {
+            map.entry(key(data)).or_insert_with(|| self.make_handle(index));
+        }
+
+        KeyLookup { map }
+    }
+
+    pub fn entry<F: Fn(&T) -> bool>(&mut self, pred: F) -> Entry<'_, T, I, S> {
+        match self.find(pred) {
+            Some(index) => Entry::Occupied(index),
+            None => Entry::Vacant(VacantEntry { array: self }),
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.find(|data| data == value).is_some()
+    }
+
+    pub fn count_where<F: Fn(&T) -> bool>(&self, pred: F) -> u32 {
+        let mut count = 0;
+        let mut index = self.valid_head;
+        while let Some(cur_index) = index {
+            let e = &self.internal_vec[cur_index as usize];
+            if pred(e.data()) {
+                count += 1;
+            }
+            index = e.next.get();
+        }
+
+        count
+    }
+
+    pub fn get_by_position(&self, pos: u32) -> Option<(u32, &T)> {
+        if pos >= self.valid_count {
+            return None;
         }
+
+        let mut index = self.valid_head?;
+        for _ in 0..pos {
+            index = self.internal_vec[index as usize].next.get()?;
+        }
+
+        Some((index, self.internal_vec[index as usize].data()))
+    }
+
+    pub fn position_of(&self, index: u32) -> Option<u32> {
+        if !self.is_valid_index(index) {
+            return None;
+        }
+
+        let mut pos = 0;
+        let mut cur_index = self.valid_head;
+        while let Some(i) = cur_index {
+            if i == index {
+                return Some(pos);
+            }
+            cur_index = self.internal_vec[i as usize].next.get();
+            pos += 1;
+        }
+
+        None
     }
 
     pub fn insert_before(&mut self, index: u32, data: &T) -> Result<u32, ErrDefine> {
@@ -154,27 +668,27 @@ impl<T: Sized + Default + Copy + Debug> QuickArray<T> {
         }
 
         let target = &self.internal_vec[index as usize];
-        assert_eq!(target.cur, index, "index calculation goes wrong");
+        debug_assert_eq!(target.cur.to_u32(), index, "index calculation goes wrong");
+        if target.cur.to_u32() != index {
+            return Err(ErrDefine::Corrupted);
+        }
 
         let target_valid = target.valid;
-        let target_pre = target.pre;
-        let target_cur = target.cur;
+        let target_pre = target.pre.get();
+        let target_cur = target.cur.to_u32();
 
         if target_valid {
-            let free_index = self.consume_ele();
-
-            match free_index {
-                Self::INVALID_INDEX => { Err(ErrDefine::ArrayIsFull) }
-                _ => {
-                    if self.valid_head == target_cur {
-                        self.valid_head = free_index;
-                    } else {
-                        self.internal_vec[target_pre as usize].next = free_index;
+            match self.consume_ele() {
+                None => { Err(ErrDefine::ArrayIsFull) }
+                Some(free_index) => {
+                    match target_pre {
+                        None => self.valid_head = Some(free_index),
+                        Some(pre) => self.internal_vec[pre as usize].next = Link::some(free_index),
                     }
-                    self.internal_vec[free_index as usize].pre = target_pre;
-                    self.internal_vec[free_index as usize].next = target_cur;
-                    self.internal_vec[free_index as usize].data = *data;
-                    self.internal_vec[target_cur as usize].pre = free_index;
+                    self.internal_vec[free_index as usize].pre = Link::new(target_pre);
+                    self.internal_vec[free_index as usize].next = Link::some(target_cur);
+                    self.internal_vec[free_index as usize].data = MaybeUninit::new(*data);
+                    self.internal_vec[target_cur as usize].pre = Link::some(free_index);
 
                     Ok(free_index)
                 }
@@ -190,27 +704,27 @@ impl<T: Sized + Default + Copy + Debug> QuickArray<T> {
         }
 
         let target = &self.internal_vec[index as usize];
-        assert_eq!(target.cur, index, "index calculation goes wrong");
+        debug_assert_eq!(target.cur.to_u32(), index, "index calculation goes wrong");
+        if target.cur.to_u32() != index {
+            return Err(ErrDefine::Corrupted);
+        }
 
         let target_valid = target.valid;
-        let target_next = target.next;
-        let target_cur = target.cur;
+        let target_next = target.next.get();
+        let target_cur = target.cur.to_u32();
 
         if target_valid {
-            let free_index = self.consume_ele();
-
-            match free_index {
-                Self::INVALID_INDEX => { Err(ErrDefine::ArrayIsFull) }
-                _ => {
-                    if self.valid_tail == target_cur {
-                        self.valid_tail = free_index;
-                    } else {
-                        self.internal_vec[target_next as usize].pre = free_index;
+            match self.consume_ele() {
+                None => { Err(ErrDefine::ArrayIsFull) }
+                Some(free_index) => {
+                    match target_next {
+                        None => self.valid_tail = Some(free_index),
+                        Some(next) => self.internal_vec[next as usize].pre = Link::some(free_index),
                     }
-                    self.internal_vec[free_index as usize].pre = target_cur;
-                    self.internal_vec[free_index as usize].next = target_next;
-                    self.internal_vec[free_index as usize].data = *data;
-                    self.internal_vec[target_cur as usize].next = free_index;
+                    self.internal_vec[free_index as usize].pre = Link::some(target_cur);
+                    self.internal_vec[free_index as usize].next = Link::new(target_next);
+                    self.internal_vec[free_index as usize].data = MaybeUninit::new(*data);
+                    self.internal_vec[target_cur as usize].next = Link::some(free_index);
 
                     Ok(free_index)
                 }
@@ -220,404 +734,3946 @@ impl<T: Sized + Default + Copy + Debug> QuickArray<T> {
         }
     }
 
+    pub fn insert_at_position(&mut self, pos: u32, data: &T) -> Result<u32, ErrDefine> {
+        if pos > self.valid_count {
+            return Err(ErrDefine::InvalidIndex);
+        }
+
+        match self.get_by_position(pos) {
+            Some((index, _)) => self.insert_before(index, data),
+            None => self.push_back(data),
+        }
+    }
+
     pub fn push_back(&mut self, data: &T) -> Result<u32, ErrDefine> {
-        if self.valid_tail == Self::INVALID_INDEX {
-            let free_index = self.consume_ele();
-            match free_index {
-                Self::INVALID_INDEX => { Err(ErrDefine::ArrayIsFull) }
-                _ => {
-                    self.internal_vec[free_index as usize].data = *data;
-                    self.valid_tail = free_index;
-                    self.valid_head = free_index;
+        match self.valid_tail {
+            Some(tail) => self.insert_after(tail, data),
+            None => match self.consume_ele() {
+                None => { Err(ErrDefine::ArrayIsFull) }
+                Some(free_index) => {
+                    self.internal_vec[free_index as usize].data = MaybeUninit::new(*data);
+                    self.valid_tail = Some(free_index);
+                    self.valid_head = Some(free_index);
                     Ok(free_index)
                 }
-            }
-        } else {
-            self.insert_after(self.valid_tail, data)
+            },
         }
     }
 
     pub fn push_front(&mut self, data: &T) -> Result<u32, ErrDefine> {
-        if self.valid_head == Self::INVALID_INDEX {
-            self.push_back(data)
-        } else {
-            let free_index = self.consume_ele();
-            match free_index {
-                Self::INVALID_INDEX => { Err(ErrDefine::ArrayIsFull) }
-                _ => {
-                    self.internal_vec[free_index as usize].data = *data;
-                    self.internal_vec[free_index as usize].next = self.valid_head;
-                    self.internal_vec[self.valid_head as usize].pre = free_index;
-                    self.valid_head = free_index;
+        match self.valid_head {
+            None => self.push_back(data),
+            Some(head) => match self.consume_ele() {
+                None => { Err(ErrDefine::ArrayIsFull) }
+                Some(free_index) => {
+                    self.internal_vec[free_index as usize].data = MaybeUninit::new(*data);
+                    self.internal_vec[free_index as usize].next = Link::some(head);
+                    self.internal_vec[head as usize].pre = Link::some(free_index);
+                    self.valid_head = Some(free_index);
                     Ok(free_index)
                 }
-            }
+            },
         }
     }
 
-    pub fn remove_at(&mut self, index: u32) -> Result<(), ErrDefine> {
-        if index >= self.max_size {
-            return Err(ErrDefine::InvalidIndex);
-        }
-
-        let target = &self.internal_vec[index as usize];
-        assert_eq!(target.cur, index, "index calculation goes wrong");
-
-        let target_valid = target.valid;
-        let target_pre = target.pre;
-        let target_next = target.next;
-        let target_cur = target.cur;
+    pub fn insert_before_handle(&mut self, index: u32, data: &T) -> Result<Handle, ErrDefine> {
+        let new_index = self.insert_before(index, data)?;
+        Ok(self.make_handle(new_index))
+    }
 
-        if target_valid {
-            if self.valid_head == target_cur {
-                self.valid_head = target_next;
-            }
+    pub fn insert_after_handle(&mut self, index: u32, data: &T) -> Result<Handle, ErrDefine> {
+        let new_index = self.insert_after(index, data)?;
+        Ok(self.make_handle(new_index))
+    }
 
-            if self.valid_tail == target_cur {
-                self.valid_tail = target_pre;
-            }
+    pub fn push_back_handle(&mut self, data: &T) -> Result<Handle, ErrDefine> {
+        let new_index = self.push_back(data)?;
+        Ok(self.make_handle(new_index))
+    }
 
-            self.recycle_ele(target_cur);
+    pub fn push_front_handle(&mut self, data: &T) -> Result<Handle, ErrDefine> {
+        let new_index = self.push_front(data)?;
+        Ok(self.make_handle(new_index))
+    }
 
-            Ok(())
-        } else {
-            Err(ErrDefine::InvalidIndex)
+    /// Pushes every item to the back and returns how many were inserted. Stops with
+    /// `ArrayIsFull` at the first item that does not fit; the items pushed before it remain.
+    pub fn extend_from<Iter: IntoIterator<Item = T>>(&mut self, iter: Iter) -> Result<u32, ErrDefine> {
+        let mut count = 0;
+        for data in iter {
+            self.push_back(&data)?;
+            count += 1;
         }
+
+        Ok(count)
     }
 
-    pub fn pop_last(&mut self) -> Result<(), ErrDefine> {
-        if self.valid_tail == Self::INVALID_INDEX {
-            Err(ErrDefine::ArrayIsEmpty)
-        } else {
-            self.remove_at(self.valid_tail)
+    /// Pushes items to the back until the array is full and returns how many of the remaining
+    /// items were skipped. Unlike `extend_from` the whole iterator is consumed.
+    pub fn try_extend<Iter: IntoIterator<Item = T>>(&mut self, iter: Iter) -> u32 {
+        let mut skipped = 0;
+        for data in iter {
+            if self.push_back(&data).is_err() {
+                skipped += 1;
+            }
         }
+
+        skipped
     }
 
-    pub fn update_at(&mut self, index: u32, data: &T) -> Result<(), ErrDefine> {
+    /// Pushes `data` to the front, evicting the tail element first when the array is full.
+    /// Returns the new slot index and the evicted value, if any.
+    pub fn push_front_evicting(&mut self, data: &T) -> (u32, Option<T>) {
+        let mut evicted = None;
+        if self.is_full() {
+            evicted = self.get_tail_element().copied();
+            self.pop_last().expect("full array must have a tail");
+        }
+
+        let index = self.push_front(data).expect("array must have a free slot after eviction");
+        (index, evicted)
+    }
+
+    pub fn insert_sorted<F: Fn(&T, &T) -> Ordering>(&mut self, data: &T, cmp: F) -> Result<u32, ErrDefine> {
+        let mut index = self.valid_head;
+        while let Some(cur_index) = index {
+            let e = &self.internal_vec[cur_index as usize];
+            if cmp(data, e.data()) == Ordering::Less {
+                return self.insert_before(cur_index, data);
+            }
+            index = e.next.get();
+        }
+
+        self.push_back(data)
+    }
+
+    /// Removes the element at `index` and returns its value.
+    pub fn remove_at(&mut self, index: u32) -> Result<T, ErrDefine> {
         if index >= self.max_size {
             return Err(ErrDefine::InvalidIndex);
         }
 
-        let target = &mut self.internal_vec[index as usize];
-        assert_eq!(target.cur, index, "index calculation goes wrong");
+        let target = &self.internal_vec[index as usize];
+        debug_assert_eq!(target.cur.to_u32(), index, "index calculation goes wrong");
+        if target.cur.to_u32() != index {
+            return Err(ErrDefine::Corrupted);
+        }
 
-        if target.valid {
-            target.data = *data;
-            Ok(())
+        let target_valid = target.valid;
+        let target_pre = target.pre.get();
+        let target_next = target.next.get();
+        let target_cur = target.cur.to_u32();
+
+        if target_valid {
+            if self.valid_head == Some(target_cur) {
+                self.valid_head = target_next;
+            }
+
+            if self.valid_tail == Some(target_cur) {
+                self.valid_tail = target_pre;
+            }
+
+            let removed = *self.internal_vec[target_cur as usize].data();
+            self.recycle_ele(target_cur);
+
+            Ok(removed)
         } else {
             Err(ErrDefine::InvalidIndex)
         }
     }
 
-    pub fn expand_to(&mut self, new_size: u32) -> Result<(), ErrDefine> {
-        if new_size <= self.max_size || new_size >= Self::INVALID_INDEX {
-            Err(ErrDefine::ArraySizeError)
-        } else {
-            let mut expand_vec: Vec<QuickElement<T>> = Vec::with_capacity(new_size as usize);
-            for _ in 0..new_size {
-                expand_vec.push(QuickElement::<T>::default());
-            }
+    /// Removes the element at `index` in O(1) by moving the tail element's data into its slot,
+    /// and returns the removed value. Unlike `remove_at` this changes the list order, and the
+    /// tail slot is recycled instead: handles and indices to the old tail no longer resolve,
+    /// while those to `index` now refer to the moved value.
+    pub fn swap_remove(&mut self, index: u32) -> Result<T, ErrDefine> {
+        let tail = match self.valid_tail {
+            Some(tail) if self.is_valid_index(index) => tail,
+            _ => return Err(ErrDefine::InvalidIndex),
+        };
 
-            for i in 0..self.max_size {
-                expand_vec[i as usize] = self.internal_vec[i as usize];
-            }
+        let removed = *self.internal_vec[index as usize].data();
+        if index != tail {
+            self.internal_vec[index as usize].data = self.internal_vec[tail as usize].data;
+        }
+        self.remove_at(tail)?;
+
+        Ok(removed)
+    }
+
+    pub fn remove_value(&mut self, value: &T) -> Option<u32>
+    where
+        T: PartialEq,
+    {
+        let index = self.find(|data| data == value)?;
+        self.remove_at(index).ok()?;
+        Some(index)
+    }
 
-            for i in (self.max_size + 1)..(new_size - 1) {
-                expand_vec[i as usize].pre = i - 1;
-                expand_vec[i as usize].next = i + 1;
-                expand_vec[i as usize].cur = i;
+    /// Removes every valid slot in `indices` and returns how many were removed. Out of range,
+    /// free and repeated indices are skipped.
+    pub fn remove_many(&mut self, indices: &[u32]) -> u32 {
+        let mut count = 0;
+        for index in indices {
+            if self.is_valid_index(*index) && self.remove_at(*index).is_ok() {
+                count += 1;
             }
+        }
 
-            expand_vec[self.max_size as usize].pre = Self::INVALID_INDEX;
-            expand_vec[self.max_size as usize].next = self.max_size + 1;
-            expand_vec[self.max_size as usize].cur = self.max_size;
+        count
+    }
 
-            expand_vec[new_size as usize - 1].pre = new_size - 2;
-            expand_vec[new_size as usize - 1].next = self.free_head;
-            expand_vec[new_size as usize - 1].cur = new_size - 1;
+    /// Keeps only the elements for which `f` returns `true`, in one pass over the list.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_visit(|_, data| f(data));
+    }
 
-            self.internal_vec = expand_vec;
-            self.free_head = self.max_size;
-            self.max_size = new_size;
+    /// Like `retain`, but `f` may also update the elements it keeps.
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        let mut index = self.valid_head;
+        while let Some(cur_index) = index {
+            let e = &mut self.internal_vec[cur_index as usize];
+            index = e.next.get();
+            if !f(e.data_mut()) {
+                self.remove_at(cur_index).expect("slot in the valid list must be removable");
+            }
+        }
+    }
 
-            Ok(())
+    /// Visits the elements in list order and removes those for which `f` returns `false`. The
+    /// next slot is read before `f` runs, so pruning during the walk is safe; use this instead
+    /// of collecting indices from `iter`, whose borrow rules out removal mid-iteration.
+    pub fn retain_visit<F: FnMut(u32, &T) -> bool>(&mut self, mut f: F) {
+        let mut index = self.valid_head;
+        while let Some(cur_index) = index {
+            let e = &self.internal_vec[cur_index as usize];
+            index = e.next.get();
+            if !f(cur_index, e.data()) {
+                self.remove_at(cur_index).expect("slot in the valid list must be removable");
+            }
         }
     }
 
-    fn init(&mut self) {
-        match self.max_size {
-            1 => {
-                self.internal_vec[0].pre = Self::INVALID_INDEX;
-                self.internal_vec[0].next = Self::INVALID_INDEX;
-                self.internal_vec[0].cur = 0;
-            },
-            _ => {
-                for i in 1..(self.max_size - 1) {
-                    self.internal_vec[i as usize].pre = (i as usize - 1) as u32;
-                    self.internal_vec[i as usize].next = (i as usize + 1) as u32;
-                    self.internal_vec[i as usize].cur = i;
-                }
+    /// Removes up to `count` elements starting at logical position `start` and returns how
+    /// many were removed.
+    pub fn remove_range(&mut self, start: u32, count: u32) -> Result<u32, ErrDefine> {
+        if start > self.valid_count {
+            return Err(ErrDefine::InvalidIndex);
+        }
+
+        let count = count.min(self.valid_count - start);
+        let mut index = self.get_by_position(start).map(|item| item.0);
+        for _ in 0..count {
+            let cur_index = index.ok_or(ErrDefine::InvalidIndex)?;
+            index = self.internal_vec[cur_index as usize].next.get();
+            self.remove_at(cur_index)?;
+        }
+
+        Ok(count)
+    }
+
+    /// Removes every element whose key equals the key of the last kept element before it in
+    /// list order, like `Vec::dedup_by_key`. Kept elements stay in their slots.
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        let head = match self.valid_head {
+            Some(head) => head,
+            None => return,
+        };
 
-                self.internal_vec[0].pre = Self::INVALID_INDEX;
-                self.internal_vec[0].next = 1;
-                self.internal_vec[0].cur = 0;
+        let mut kept_key = key(self.internal_vec[head as usize].data());
+        let mut index = self.internal_vec[head as usize].next.get();
+        while let Some(cur_index) = index {
+            let e = &self.internal_vec[cur_index as usize];
+            index = e.next.get();
+            let cur_key = key(e.data());
+            if cur_key == kept_key {
+                self.remove_at(cur_index).expect("slot in the valid list must be removable");
+            } else {
+                kept_key = cur_key;
+            }
+        }
+    }
 
-                self.internal_vec[self.max_size as usize - 1].pre = self.max_size - 2;
-                self.internal_vec[self.max_size as usize - 1].next = Self::INVALID_INDEX;
-                self.internal_vec[self.max_size as usize - 1].cur = self.max_size - 1;
+    /// Removes elements from the head while `pred` holds and returns them in list order.
+    pub fn drain_while<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Vec<T> {
+        let mut result = Vec::new();
+        while let Some(head) = self.valid_head {
+            let data = *self.internal_vec[head as usize].data();
+            if !pred(&data) {
+                break;
             }
+            self.remove_at(head).expect("head slot must be removable");
+            result.push(data);
         }
 
+        result
     }
 
-    fn recycle_ele(&mut self, index: u32) {
-        let target_pre = self.internal_vec[index as usize].pre;
-        let target_next = self.internal_vec[index as usize].next;
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.valid_head?;
+        self.remove_at(head).ok()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.valid_tail?;
+        self.remove_at(tail).ok()
+    }
 
-        if target_pre != Self::INVALID_INDEX {
-            self.internal_vec[target_pre as usize].next = target_next;
+    pub fn pop_last(&mut self) -> Result<(), ErrDefine> {
+        match self.valid_tail {
+            None => Err(ErrDefine::ArrayIsEmpty),
+            Some(tail) => self.remove_at(tail).map(|_| ()),
         }
+    }
 
-        if target_next != Self::INVALID_INDEX {
-            self.internal_vec[target_next as usize].pre = target_pre;
+    /// Removes elements from the back until at most `len` remain.
+    pub fn truncate(&mut self, len: u32) {
+        while self.valid_count > len {
+            self.pop_back();
         }
+    }
 
-        self.internal_vec[index as usize].pre = Self::INVALID_INDEX;
-        self.internal_vec[index as usize].next = self.free_head;
-        self.internal_vec[index as usize].valid = false;
+    /// Moves all elements of `other` to the back of `self`, leaving `other` empty. Fails with
+    /// `ArrayIsFull` without touching either array if they do not fit.
+    pub fn append(&mut self, other: &mut Self) -> Result<(), ErrDefine> {
+        if other.valid_count > self.available_capacity() {
+            return Err(ErrDefine::ArrayIsFull);
+        }
 
-        if self.free_head != Self::INVALID_INDEX {
-            self.internal_vec[self.free_head as usize].pre = index;
+        for (_, data) in other.iter() {
+            self.push_back(data)?;
         }
-        self.free_head = index;
-        self.valid_count -= 1;
+        other.clear();
+
+        Ok(())
     }
 
-    fn consume_ele(&mut self) -> u32 {
-        if self.free_head ==  Self::INVALID_INDEX {
-            Self::INVALID_INDEX
-        } else {
-            let free_real_index = self.free_head;
-            self.free_head = self.internal_vec[free_real_index as usize].next as u32;
+    /// Merges the elements of `other` into `self`, both already sorted by `cmp`, keeping the
+    /// result sorted and leaving `other` empty. Equal elements of `self` stay ahead of those
+    /// from `other`. Fails with `ArrayIsFull` without touching either array if they do not fit.
+    pub fn merge_sorted<F: Fn(&T, &T) -> Ordering>(&mut self, other: &mut Self, cmp: F) -> Result<(), ErrDefine> {
+        if other.valid_count > self.available_capacity() {
+            return Err(ErrDefine::ArrayIsFull);
+        }
 
-            if self.free_head != Self::INVALID_INDEX {
-                self.internal_vec[self.free_head as usize].pre = Self::INVALID_INDEX;
+        let mut index = self.valid_head;
+        for (_, data) in other.iter() {
+            while let Some(cur_index) = index {
+                if cmp(self.internal_vec[cur_index as usize].data(), data) == Ordering::Greater {
+                    break;
+                }
+                index = self.internal_vec[cur_index as usize].next.get();
             }
 
-            self.internal_vec[free_real_index as usize].next = Self::INVALID_INDEX;
-            self.internal_vec[free_real_index as usize].valid = true;
-            self.valid_count += 1;
-            free_real_index
+            match index {
+                Some(cur_index) => self.insert_before(cur_index, data)?,
+                None => self.push_back(data)?,
+            };
         }
-    }
+        other.clear();
 
-    pub fn enumerate<'life_of_array> (self: &'life_of_array Self) -> QuickArrayIterator<'life_of_array, T> {
-        QuickArrayIterator::<'life_of_array, T> {
-            array: self,
-            index: self.valid_head,
-        }
+        Ok(())
     }
-}
 
-pub struct QuickArrayIterator<'a, T: Sized + Default + Copy + Debug> {
-    pub array : &'a QuickArray<T>,
-    pub index: u32,
-}
+    pub fn update_at(&mut self, index: u32, data: &T) -> Result<(), ErrDefine> {
+        if index >= self.max_size {
+            return Err(ErrDefine::InvalidIndex);
+        }
 
-impl<'a, T: Sized + Default + Copy + Debug> Iterator for QuickArrayIterator<'a, T> {
-    type Item = (u32, &'a T);
+        let target = &mut self.internal_vec[index as usize];
+        debug_assert_eq!(target.cur.to_u32(), index, "index calculation goes wrong");
+        if target.cur.to_u32() != index {
+            return Err(ErrDefine::Corrupted);
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let cur_ele = self.array.get_element(self.index);
-        let cur_index = self.index;
-        let next_index = self.array.get_next_index(self.index);
-        match next_index {
-            Some(i) => { self.index = i; }
-            None => { self.index = QuickArray::<T>::INVALID_INDEX; }
+        if target.valid {
+            target.data = MaybeUninit::new(*data);
+            Ok(())
+        } else {
+            Err(ErrDefine::InvalidIndex)
         }
+    }
 
-        match cur_ele {
-            None => { None }
-            _ => { Some((cur_index, cur_ele.unwrap())) }
+    pub fn replace_front(&mut self, data: &T) -> Result<T, ErrDefine> {
+        match self.valid_head {
+            None => Err(ErrDefine::ArrayIsEmpty),
+            Some(head) => Ok(mem::replace(self.internal_vec[head as usize].data_mut(), *data)),
         }
     }
-}
 
+    pub fn replace_back(&mut self, data: &T) -> Result<T, ErrDefine> {
+        match self.valid_tail {
+            None => Err(ErrDefine::ArrayIsEmpty),
+            Some(tail) => Ok(mem::replace(self.internal_vec[tail as usize].data_mut(), *data)),
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use crate::*;
-    use std::borrow::Borrow;
-    use std::fmt::Debug;
-    use crate::quick_array::ErrDefine;
+    pub fn update_with<F: FnOnce(&mut T)>(&mut self, index: u32, f: F) -> Result<(), ErrDefine> {
+        if !self.is_valid_index(index) {
+            return Err(ErrDefine::InvalidIndex);
+        }
 
-    fn display_array<T: Sized + Default + Copy + Debug>(array: &QuickArray<T>) {
-        println!("{:?}", array);
-        println!("=================================================");
+        f(self.internal_vec[index as usize].data_mut());
+        Ok(())
     }
 
-    #[test]
-    fn it_works() {
-        println!("array with 1 element init");
-        let mut test_array = QuickArray::<u32>::new(1);
+    pub fn for_each_mut<F: FnMut(u32, &mut T)>(&mut self, mut f: F) {
+        let mut index = self.valid_head;
+        while let Some(cur_index) = index {
+            let e = &mut self.internal_vec[cur_index as usize];
+            f(cur_index, e.data_mut());
+            index = e.next.get();
+        }
+    }
 
-        println!("array init");
-        let mut test_array = QuickArray::<u32>::new(5);
-        display_array(&test_array);
+    /// Returns mutable references to the data of `N` distinct slots at once. Returns `None` if
+    /// any index is out of range, refers to a free slot, or appears more than once.
+    pub fn get_many_mut<const N: usize>(&mut self, indices: [u32; N]) -> Option<[&mut T; N]> {
+        for (i, index) in indices.iter().enumerate() {
+            if !self.is_valid_index(*index) || indices[..i].contains(index) {
+                return None;
+            }
+        }
 
-        println!("array push 111");
-        let result: Result<u32, ErrDefine> = test_array.push_back(111_u32.borrow());
-        display_array(&test_array);
+        let base = self.internal_vec.as_mut_ptr();
+        // SAFETY: every index is in range and the indices are pairwise distinct, so the
+        // references point at disjoint elements of the vec, which stays borrowed mutably
+        Some(indices.map(|index| unsafe { (*base.add(index as usize)).data_mut() }))
+    }
 
-        println!("array insert 222 after 0");
-        let result: Result<u32, ErrDefine> = test_array.insert_after(0, 222_u32.borrow());
-        display_array(&test_array);
+    pub fn swap(&mut self, a: u32, b: u32) -> Result<(), ErrDefine> {
+        if a >= self.max_size || b >= self.max_size {
+            return Err(ErrDefine::InvalidIndex);
+        }
 
-        println!("array insert 333 after 0");
-        let result: Result<u32, ErrDefine> = test_array.insert_after(0, 333_u32.borrow());
-        display_array(&test_array);
+        if !self.internal_vec[a as usize].valid || !self.internal_vec[b as usize].valid {
+            return Err(ErrDefine::InvalidIndex);
+        }
 
-        println!("array remove at 1");
-        let result: Result<(), ErrDefine> = test_array.remove_at(1);
-        display_array(&test_array);
+        if a != b {
+            let data_a = self.internal_vec[a as usize].data;
+            self.internal_vec[a as usize].data = self.internal_vec[b as usize].data;
+            self.internal_vec[b as usize].data = data_a;
+        }
 
-        println!("array pop last");
-        let result: Result<(), ErrDefine> = test_array.pop_last();
-        display_array(&test_array);
+        Ok(())
+    }
 
-        println!("array pop last");
-        let result: Result<(), ErrDefine> = test_array.pop_last();
-        display_array(&test_array);
+    /// Sorts the valid elements by relinking the list. Data never moves between slots, so
+    /// slot indices held by the caller keep pointing at the same values.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut cmp: F) {
+        let mut indices: Vec<u32> = self.indices().collect();
+        let internal_vec = &self.internal_vec;
+        indices.sort_by(|a, b| cmp(internal_vec[*a as usize].data(), internal_vec[*b as usize].data()));
+        self.relink(&indices);
+    }
 
-        println!("array pop last");
-        let result: Result<(), ErrDefine> = test_array.pop_last();
-        display_array(&test_array);
-        match result {
-            Err(ErrDefine::ArrayIsEmpty) => { println!("Array is empty") }
-            _ => { () }
+    /// Moves the first `n` elements (modulo the element count) to the back by relinking the
+    /// list ends; no data is moved and slot indices stay valid.
+    pub fn rotate_left(&mut self, n: u32) {
+        let n = if self.valid_count > 1 { n % self.valid_count } else { 0 };
+        if n == 0 {
+            return;
         }
 
-        println!("array push 4 numbers");
-        let result: Result<u32, ErrDefine> = test_array.push_back(444_u32.borrow());
-        display_array(&test_array);
+        let (new_head, _) = self.get_by_position(n).expect("position is below valid_count");
+        let new_tail = self.internal_vec[new_head as usize].pre.get().expect("new head is not the first element");
+        let head = self.valid_head.expect("array is not empty");
+        let tail = self.valid_tail.expect("array is not empty");
 
-        let result: Result<u32, ErrDefine> = test_array.push_front(4444_u32.borrow());
-        display_array(&test_array);
+        self.internal_vec[tail as usize].next = Link::some(head);
+        self.internal_vec[head as usize].pre = Link::some(tail);
+        self.internal_vec[new_tail as usize].next = Link::NONE;
+        self.internal_vec[new_head as usize].pre = Link::NONE;
 
-        let result: Result<u32, ErrDefine> = test_array.push_back(44444_u32.borrow());
-        display_array(&test_array);
+        self.valid_head = Some(new_head);
+        self.valid_tail = Some(new_tail);
+    }
 
-        let result: Result<u32, ErrDefine> = test_array.push_back(444444_u32.borrow());
-        display_array(&test_array);
+    /// Moves the valid slot `index` to the head of the list without changing its slot or
+    /// handle, e.g. to mark a cache entry as most recently used.
+    pub fn move_to_front(&mut self, index: u32) -> Result<(), ErrDefine> {
+        if !self.is_valid_index(index) {
+            return Err(ErrDefine::InvalidIndex);
+        }
+        let head = self.valid_head.expect("array is not empty");
+        if head == index {
+            return Ok(());
+        }
 
-        let result: Result<u32, ErrDefine> = test_array.push_back(4444444_u32.borrow());
-        display_array(&test_array);
+        let pre = self.internal_vec[index as usize].pre.get();
+        let next = self.internal_vec[index as usize].next.get();
+        if let Some(pre) = pre {
+            self.internal_vec[pre as usize].next = Link::new(next);
+        }
+        match next {
+            Some(next) => self.internal_vec[next as usize].pre = Link::new(pre),
+            None => self.valid_tail = pre,
+        }
 
-        println!("array push 6 111");
-        let result: Result<u32, ErrDefine> = test_array.push_back(111_u32.borrow());
-        display_array(&test_array);
-        match result {
-            Err(ErrDefine::ArrayIsFull) => { println!("Array is full") }
-            _ => { () }
+        self.internal_vec[index as usize].pre = Link::NONE;
+        self.internal_vec[index as usize].next = Link::some(head);
+        self.internal_vec[head as usize].pre = Link::some(index);
+        self.valid_head = Some(index);
+        Ok(())
+    }
+
+    /// Moves the valid elements into slots `0..valid_count` in list order and rebuilds the free
+    /// list over the remaining slots, so iteration walks memory linearly. Slot indices and
+    /// handles held by the caller are invalidated; the returned `(old, new)` pairs, in list
+    /// order, map each old slot index to its new one.
+    pub fn compact(&mut self) -> Vec<(u32, u32)> {
+        let remap: Vec<(u32, u32)> = self.indices().zip(0..).collect();
+        let values = self.to_vec();
+        self.clear();
+
+        for data in &values {
+            self.push_back(data).expect("valid elements must fit the cleared array");
         }
 
-        println!("array update 999 at 0");
-        let result = test_array.update_at(0, 999_u32.borrow());
-        display_array(&test_array);
+        remap
+    }
 
-        let ele = test_array.get_head_element();
-        println!("head value is {}", ele.unwrap());
+    /// Verifies the internal invariants and describes the first violation found: every slot's
+    /// `cur` matches its position, the valid and free lists are consistent doubly linked lists
+    /// that together cover every slot exactly once, and `valid_count`/`valid_tail` agree with
+    /// the valid list.
+    pub fn check_integrity(&self) -> Result<(), String> {
+        if self.internal_vec.len() != self.max_size as usize {
+            return Err(format!("internal vec has {} slots, expected {}", self.internal_vec.len(), self.max_size));
+        }
 
-        let ele = test_array.get_tail_element();
-        println!("tail value is {}", ele.unwrap());
-        display_array(&test_array);
+        for (i, e) in self.internal_vec.iter().enumerate() {
+            if e.cur.to_u32() != i as u32 {
+                return Err(format!("slot {} has cur {}", i, e.cur.to_u32()));
+            }
+        }
 
-        println!("expand array to 10");
-        test_array.expand_to(10);
-        display_array(&test_array);
+        let mut seen = vec![false; self.max_size as usize];
+        let (valid_len, valid_last) = self.check_list("valid", self.valid_head, true, &mut seen)?;
+        if valid_len != self.valid_count {
+            return Err(format!("valid list has {} elements, valid_count is {}", valid_len, self.valid_count));
+        }
+        if valid_last != self.valid_tail {
+            return Err(format!("valid list ends at {:?}, valid_tail is {:?}", valid_last, self.valid_tail));
+        }
 
-        let result: Result<u32, ErrDefine> = test_array.push_back(888_u32.borrow());
-        display_array(&test_array);
+        let (free_len, _) = self.check_list("free", self.free_head, false, &mut seen)?;
+        if valid_len + free_len != self.max_size {
+            let missing = seen.iter().position(|visited| !visited).unwrap_or(0);
+            return Err(format!("slot {} is in neither the valid nor the free list", missing));
+        }
 
-        let result: Result<(), ErrDefine> = test_array.pop_last();
-        display_array(&test_array);
+        Ok(())
+    }
 
-        test_array.push_front(666_u32.borrow());
-        display_array(&test_array);
+    fn check_list(&self, name: &str, head: Option<u32>, valid: bool, seen: &mut [bool]) -> Result<(u32, Option<u32>), String> {
+        let mut len = 0;
+        let mut pre = None;
+        let mut index = head;
+        while let Some(cur_index) = index {
+            if cur_index >= self.max_size {
+                return Err(format!("{} list links to out of range slot {}", name, cur_index));
+            }
+            if seen[cur_index as usize] {
+                return Err(format!("slot {} is reached twice while walking the {} list", cur_index, name));
+            }
+            seen[cur_index as usize] = true;
 
-        for (i, e) in test_array.enumerate() {
-            println!("{}:{}", i, e)
+            let e = &self.internal_vec[cur_index as usize];
+            if e.valid != valid {
+                return Err(format!("slot {} in the {} list has valid flag {}", cur_index, name, e.valid));
+            }
+            if e.pre.get() != pre {
+                return Err(format!("slot {} in the {} list has pre {:?}, expected {:?}", cur_index, name, e.pre, pre));
+            }
+
+            len += 1;
+            pre = Some(cur_index);
+            index = e.next.get();
         }
+
+        Ok((len, pre))
     }
 
-    #[test]
-    fn test_lru(){
-        const LRU_LEN:u32=3;
-        let mut array_obj= QuickArray::<i32>::new(LRU_LEN);
-        let mut push_fn=|val: i32|{
-            if array_obj.is_full(){
-                {
-                    let last_item= array_obj.get_tail_element().unwrap();
-                    assert_eq!(*last_item, val-(LRU_LEN as i32));
+    fn init(&mut self) {
+        match self.max_size {
+            1 => {
+                self.internal_vec[0].pre = Link::NONE;
+                self.internal_vec[0].next = Link::NONE;
+                self.internal_vec[0].cur = I::from_u32(0);
+            },
+            _ => {
+                for i in 1..(self.max_size - 1) {
+                    self.internal_vec[i as usize].pre = Link::some(i - 1);
+                    self.internal_vec[i as usize].next = Link::some(i + 1);
+                    self.internal_vec[i as usize].cur = I::from_u32(i);
                 }
-                array_obj.pop_last().expect("pop last error");
+
+                self.internal_vec[0].pre = Link::NONE;
+                self.internal_vec[0].next = Link::some(1);
+                self.internal_vec[0].cur = I::from_u32(0);
+
+                self.internal_vec[self.max_size as usize - 1].pre = Link::some(self.max_size - 2);
+                self.internal_vec[self.max_size as usize - 1].next = Link::NONE;
+                self.internal_vec[self.max_size as usize - 1].cur = I::from_u32(self.max_size - 1);
             }
+        }
 
-            array_obj.push_front(&val).expect("push error");
-            if val>=LRU_LEN as i32{
-                assert_eq!( array_obj.get_valid_count(),LRU_LEN);
-            }else{
-                assert_eq!( array_obj.get_valid_count(),val as u32);
+        for e in self.internal_vec.iter_mut() {
+            if e.valid {
+                e.gen = e.gen.wrapping_add(1);
             }
-        };
+            e.valid = false;
+        }
+    }
 
-        push_fn(1);
-        push_fn(2);
-        push_fn(3);
-        push_fn(4);
-        push_fn(5);
-        push_fn(6);
-        push_fn(7);
-        push_fn(8);
-        push_fn(9);
-        push_fn(10);
+    fn make_handle(&self, index: u32) -> Handle {
+        Handle {
+            index,
+            gen: self.internal_vec[index as usize].gen,
+        }
     }
 
-    #[test]
-    fn test_normal(){
-        const LRU_LEN:u32=5;
-        // 缓存未满的情况
-        let total_data=vec![1,2];
-        let mut array_obj= QuickArray::<i32>::new(LRU_LEN);
-        for item in &total_data{
-            array_obj.push_front(item).expect("push error");
+    fn relink(&mut self, indices: &[u32]) {
+        let mut pre = None;
+        for (i, index) in indices.iter().enumerate() {
+            let next = indices.get(i + 1).copied();
+            self.internal_vec[*index as usize].pre = Link::new(pre);
+            self.internal_vec[*index as usize].next = Link::new(next);
+            pre = Some(*index);
         }
-        let array_val:Vec<i32>= array_obj.enumerate().map(|item|*item.1) .collect();
-        assert_eq!(total_data.len(),array_val.len());
-        assert_eq!(total_data.len(),array_obj.get_valid_count() as usize);
-        for index in 0..array_val.len(){
-            assert_eq!(array_val[index],total_data[total_data.len()-index-1])
+
+        self.valid_head = indices.first().copied();
+        self.valid_tail = pre;
+    }
+
+    fn recycle_ele(&mut self, index: u32) {
+        let target_pre = self.internal_vec[index as usize].pre.get();
+        let target_next = self.internal_vec[index as usize].next.get();
+
+        if let Some(pre) = target_pre {
+            self.internal_vec[pre as usize].next = Link::new(target_next);
         }
 
-        // 清空测试
-        array_obj.clear();
-        assert_eq!(array_obj.get_valid_count(),0);
-        let array_val:Vec<i32>= array_obj.enumerate().map(|item|*item.1) .collect();
-        assert_eq!(array_val.len(),0);
+        if let Some(next) = target_next {
+            self.internal_vec[next as usize].pre = Link::new(target_pre);
+        }
 
-        // 链满的情况
-        let total_data=vec![1,2,3,4,5,6,7,8,9];
+        self.internal_vec[index as usize].pre = Link::NONE;
+        self.internal_vec[index as usize].next = Link::new(self.free_head);
+        self.internal_vec[index as usize].valid = false;
+        self.internal_vec[index as usize].gen = self.internal_vec[index as usize].gen.wrapping_add(1);
+
+        if let Some(free_head) = self.free_head {
+            self.internal_vec[free_head as usize].pre = Link::some(index);
+        }
+        self.free_head = Some(index);
+        self.valid_count -= 1;
+    }
+
+    /// Takes the first free slot, unlinked and marked valid, or `None` if the array is full.
+    fn consume_ele(&mut self) -> Option<u32> {
+        let free_real_index = self.free_head?;
+        self.free_head = self.internal_vec[free_real_index as usize].next.get();
+
+        if let Some(free_head) = self.free_head {
+            self.internal_vec[free_head as usize].pre = Link::NONE;
+        }
+
+        self.internal_vec[free_real_index as usize].pre = Link::NONE;
+        self.internal_vec[free_real_index as usize].next = Link::NONE;
+        self.internal_vec[free_real_index as usize].valid = true;
+        self.valid_count += 1;
+        Some(free_real_index)
+    }
+
+    pub fn export_raw(&self) -> RawSnapshot<T> {
+        RawSnapshot {
+            max_size: self.max_size,
+            free_head: self.free_head,
+            valid_head: self.valid_head,
+            valid_tail: self.valid_tail,
+            valid_count: self.valid_count,
+            elements: self
+                .internal_vec
+                .iter()
+                .map(|e| {
+                    let data = if e.valid { Some(*e.data()) } else { None };
+                    (data, e.pre.get(), e.next.get(), e.cur.to_u32(), e.valid)
+                })
+                .collect(),
+        }
+    }
+
+    /// Encodes the whole structure, links and handle generations included, as a versioned
+    /// binary snapshot that `from_bytes` loads without relinking. All integers are little
+    /// endian; after the magic `QARR` and the version come `max_size`, `valid_count`, the
+    /// three list heads, the element size and then one record per slot: valid flag, `pre`,
+    /// `next`, `gen` and the element bytes (zeroed for free slots). Missing links are stored
+    /// as `u32::MAX`.
+    pub fn to_bytes(&self) -> Vec<u8>
+    where
+        T: ElementBytes,
+    {
+        let link = |index: Option<u32>| index.unwrap_or(NO_LINK);
+        let mut out = Vec::with_capacity(32 + self.internal_vec.len() * (13 + T::SIZE));
+        out.extend_from_slice(SNAPSHOT_MAGIC);
+        for value in &[
+            SNAPSHOT_VERSION,
+            self.max_size,
+            self.valid_count,
+            link(self.free_head),
+            link(self.valid_head),
+            link(self.valid_tail),
+            T::SIZE as u32,
+        ] {
+            value.write_bytes(&mut out);
+        }
+
+        for e in self.internal_vec.iter() {
+            out.push(e.valid as u8);
+            link(e.pre.get()).write_bytes(&mut out);
+            link(e.next.get()).write_bytes(&mut out);
+            e.gen.write_bytes(&mut out);
+            if e.valid {
+                e.data().write_bytes(&mut out);
+            } else {
+                out.resize(out.len() + T::SIZE, 0);
+            }
+        }
+
+        out
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        let mut result = Vec::with_capacity(self.valid_count as usize);
+        let mut index = self.valid_head;
+        while let Some(cur_index) = index {
+            let e = &self.internal_vec[cur_index as usize];
+            result.push(*e.data());
+            index = e.next.get();
+        }
+
+        result
+    }
+
+    /// Removes all elements, yielding them in list order. The array is left empty with its free
+    /// list rebuilt once the returned iterator is dropped.
+    pub fn drain(&mut self) -> QuickArrayDrain<'_, T, I, S> {
+        QuickArrayDrain { array: self }
+    }
+
+    /// Lazily removes and yields the elements matching `pred` in list order; the others keep
+    /// their order and slots.
+    pub fn extract_if<F: FnMut(&T) -> bool>(&mut self, pred: F) -> QuickArrayExtractIf<'_, T, I, F, S> {
+        QuickArrayExtractIf {
+            index: self.valid_head,
+            array: self,
+            pred,
+        }
+    }
+
+    /// Renders the list as `head -> [index]=value -> ... -> tail (n free)`, for logs and
+    /// debugging sessions where `{:#?}` is too noisy.
+    pub fn fmt_chain(&self) -> ChainFmt<'_, T, I, S> {
+        ChainFmt(self)
+    }
+
+    /// Iterates from the tail to the head, e.g. for eviction scans from the LRU end.
+    pub fn rev_enumerate(&self) -> core::iter::Rev<QuickArrayIterator<'_, T, I, S>> {
+        self.iter().rev()
+    }
+
+    pub fn iter_mut(&mut self) -> QuickArrayIterMut<'_, T, I> {
+        QuickArrayIterMut {
+            elements: self.internal_vec.as_mut_ptr(),
+            index: self.valid_head,
+            remaining: self.valid_count,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> QuickArrayIterator<'_, T, I, S> {
+        QuickArrayIterator {
+            array: self,
+            index: self.valid_head,
+            back: self.valid_tail,
+            remaining: self.valid_count,
+        }
+    }
+
+    #[deprecated(note = "use `iter`")]
+    pub fn enumerate(&self) -> QuickArrayIterator<'_, T, I, S> {
+        self.iter()
+    }
+
+    /// Iterates from the valid slot `start` to the tail, or returns `None` if `start` is out of
+    /// range or free.
+    pub fn iter_from(&self, start: u32) -> Option<QuickArrayIterator<'_, T, I, S>> {
+        let pos = self.position_of(start)?;
+        Some(QuickArrayIterator {
+            array: self,
+            index: Some(start),
+            back: self.valid_tail,
+            remaining: self.valid_count - pos,
+        })
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType> QuickArray<T, I> {
+    pub fn new(_max_size: u32) -> Self {
+        assert!(_max_size < I::MAX_SIZE, "Quick array is too large to init!");
+        Self::with_storage(vec![QuickElement::default(); _max_size.max(1) as usize])
+    }
+
+    pub fn from_slice(data: &[T]) -> Self {
+        assert!(data.len() < I::MAX_SIZE as usize, "Quick array is too large to init!");
+        Self::try_from_slice(data).expect("slice length must fit the array")
+    }
+
+    /// Fallible `from_slice`: fails with `ArraySizeError` instead of panicking if the slice is
+    /// too long for the index type.
+    pub fn try_from_slice(data: &[T]) -> Result<Self, ErrDefine> {
+        if data.len() >= I::MAX_SIZE as usize {
+            return Err(ErrDefine::ArraySizeError);
+        }
+
+        let mut new_array = Self::new(data.len() as u32);
+        for item in data {
+            new_array.push_back(item)?;
+        }
+
+        Ok(new_array)
+    }
+
+    /// Creates an array of capacity `max_size` holding the items of `iter` in order. Fails with
+    /// `ArrayIsFull` if the iterator yields more than `max_size` items.
+    pub fn with_capacity_and_values<Iter: IntoIterator<Item = T>>(max_size: u32, iter: Iter) -> Result<Self, ErrDefine> {
+        let mut new_array = Self::new(max_size);
+        new_array.extend_from(iter)?;
+        Ok(new_array)
+    }
+
+    /// Empties the array and sets `max_size` to `new_size` (at least 1). The buffer is only
+    /// reallocated when `new_size` exceeds its capacity.
+    pub fn clear_and_resize(&mut self, new_size: u32) {
+        assert!(new_size < I::MAX_SIZE, "Quick array is too large to init!");
+        let new_size = new_size.max(1) as usize;
+        self.truncate_slots(new_size);
+        self.internal_vec.reserve_exact(new_size - self.internal_vec.len());
+        let gen = self.retired_gen;
+        self.internal_vec.resize_with(new_size, || QuickElement { gen, ..QuickElement::default() });
+        self.max_size = new_size as u32;
+
+        self.clear();
+    }
+
+    /// Bytes used by the array, counting the whole allocated buffer including link fields.
+    pub fn memory_footprint(&self) -> usize {
+        self.internal_vec.capacity() * size_of::<QuickElement<T, I>>() + size_of::<Self>()
+    }
+
+    /// Number of slots the buffer can hold without reallocating; at least `capacity()`.
+    #[inline]
+    pub fn backing_capacity(&self) -> usize {
+        self.internal_vec.capacity()
+    }
+
+    /// Releases buffer capacity beyond `max_size`, e.g. after a large `shrink_to`.
+    pub fn shrink_backing_to_fit(&mut self) {
+        self.internal_vec.shrink_to_fit();
+    }
+
+    /// Pushes `data` to the back, first growing the array by `growth` slots (or doubling it if
+    /// `growth` is 0) when it is full. Fails with `ArraySizeError` if the grown size does not
+    /// fit the index type.
+    pub fn push_back_growing(&mut self, data: &T, growth: u32) -> Result<u32, ErrDefine> {
+        if self.is_full() {
+            let growth = if growth == 0 { self.max_size } else { growth };
+            let new_size = self.max_size.checked_add(growth).ok_or(ErrDefine::ArraySizeError)?;
+            self.expand_to(new_size)?;
+        }
+
+        self.push_back(data)
+    }
+
+    /// Moves the elements from logical position `pos` onward into a new array with the same
+    /// `max_size`. The moved elements get fresh slot indices in the new array.
+    pub fn split_off(&mut self, pos: u32) -> Result<Self, ErrDefine> {
+        if pos > self.valid_count {
+            return Err(ErrDefine::InvalidIndex);
+        }
+
+        let mut other = Self::new(self.max_size);
+        let mut index = self.get_by_position(pos).map(|item| item.0);
+        while let Some(cur_index) = index {
+            let e = self.internal_vec[cur_index as usize];
+            other.push_back(e.data())?;
+            self.remove_at(cur_index)?;
+            index = e.next.get();
+        }
+
+        Ok(other)
+    }
+
+    pub fn expand_to(&mut self, new_size: u32) -> Result<(), ErrDefine> {
+        if new_size <= self.max_size || new_size >= I::MAX_SIZE {
+            Err(ErrDefine::ArraySizeError)
+        } else {
+            self.grow_to(new_size);
+            Ok(())
+        }
+    }
+
+    /// Grows `max_size` by `additional` free slots, appending them to the existing buffer. The
+    /// buffer itself grows geometrically, so repeated small reservations rarely reallocate.
+    /// Panics if the new size does not fit the index type.
+    pub fn reserve(&mut self, additional: u32) {
+        let new_size = self.max_size.checked_add(additional).filter(|size| *size < I::MAX_SIZE);
+        assert!(new_size.is_some(), "Quick array is too large to reserve!");
+        if additional > 0 {
+            self.grow_to(self.max_size + additional);
+        }
+    }
+
+    /// Reduces `max_size` to `new_size`. The valid elements are moved into the first slots in
+    /// list order, so slot indices held by the caller are no longer meaningful afterwards.
+    pub fn shrink_to(&mut self, new_size: u32) -> Result<(), ErrDefine> {
+        if new_size < self.valid_count || new_size >= self.max_size || new_size < 1 {
+            Err(ErrDefine::ArraySizeError)
+        } else {
+            let values = self.to_vec();
+            self.truncate_slots(new_size as usize);
+            self.max_size = new_size;
+            self.clear();
+
+            for data in &values {
+                self.push_back(data).expect("valid elements must fit the shrunk array");
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Drops the slots from `new_size` on, remembering the newest generation they carried.
+    fn truncate_slots(&mut self, new_size: usize) {
+        for e in self.internal_vec.iter().skip(new_size) {
+            let gen = if e.valid { e.gen.wrapping_add(1) } else { e.gen };
+            self.retired_gen = self.retired_gen.max(gen);
+        }
+        self.internal_vec.truncate(new_size);
+    }
+
+    fn grow_to(&mut self, new_size: u32) {
+        let old_size = self.max_size;
+        let old_free_head = self.free_head;
+        let gen = self.retired_gen;
+
+        // chain every new slot into the free list, in order, ahead of the old free slots
+        // amortized growth, so repeated small reservations do not copy the buffer every time
+        self.internal_vec.reserve((new_size - old_size) as usize);
+        self.internal_vec.extend((old_size..new_size).map(|i| QuickElement::<T, I> {
+            pre: if i == old_size { Link::NONE } else { Link::some(i - 1) },
+            next: if i == new_size - 1 { Link::new(old_free_head) } else { Link::some(i + 1) },
+            cur: I::from_u32(i),
+            gen,
+            ..QuickElement::default()
+        }));
+
+        if let Some(old_free_head) = old_free_head {
+            self.internal_vec[old_free_head as usize].pre = Link::some(new_size - 1);
+        }
+
+        self.free_head = Some(old_size);
+        self.max_size = new_size;
+    }
+
+    /// Rebuilds an array from a snapshot taken by `export_raw`. Fails with `ArraySizeError` if
+    /// the size does not match the elements or the index type, and with `Corrupted` if a
+    /// link is out of range or the lists fail `check_integrity`. Handle generations restart.
+    pub fn import_raw(snapshot: RawSnapshot<T>) -> Result<Self, ErrDefine> {
+        let max_size = snapshot.max_size;
+        if max_size < 1 || max_size >= I::MAX_SIZE || snapshot.elements.len() != max_size as usize {
+            return Err(ErrDefine::ArraySizeError);
+        }
+
+        let in_range = |index: Option<u32>| !matches!(index, Some(index) if index >= max_size);
+        let mut internal_vec = Vec::with_capacity(max_size as usize);
+        for (data, pre, next, cur, valid) in snapshot.elements {
+            if !in_range(pre) || !in_range(next) || cur >= max_size || valid != data.is_some() {
+                return Err(ErrDefine::Corrupted);
+            }
+
+            internal_vec.push(QuickElement {
+                data: data.map_or(MaybeUninit::uninit(), MaybeUninit::new),
+                pre: Link::new(pre),
+                next: Link::new(next),
+                cur: I::from_u32(cur),
+                valid,
+                gen: 0,
+            });
+        }
+
+        let new_array = Self {
+            max_size,
+            free_head: snapshot.free_head,
+            valid_head: snapshot.valid_head,
+            valid_tail: snapshot.valid_tail,
+            valid_count: snapshot.valid_count,
+            retired_gen: 0,
+            internal_vec,
+            marker: PhantomData,
+        };
+        if new_array.check_integrity().is_err() {
+            return Err(ErrDefine::Corrupted);
+        }
+
+        Ok(new_array)
+    }
+
+    /// Loads a snapshot written by `to_bytes`. Fails with `ArraySizeError` if the size does
+    /// not fit the index type and with `Corrupted` if the bytes are malformed, of another
+    /// version or element size, or describe inconsistent lists.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ErrDefine>
+    where
+        T: ElementBytes,
+    {
+        let mut reader = ByteReader { bytes, pos: 0 };
+        if reader.take(4)? != SNAPSHOT_MAGIC || reader.u32()? != SNAPSHOT_VERSION {
+            return Err(ErrDefine::Corrupted);
+        }
+
+        let max_size = reader.u32()?;
+        let valid_count = reader.u32()?;
+        let free_head = reader.link()?;
+        let valid_head = reader.link()?;
+        let valid_tail = reader.link()?;
+        if reader.u32()? as usize != T::SIZE {
+            return Err(ErrDefine::Corrupted);
+        }
+        if max_size < 1 || max_size >= I::MAX_SIZE {
+            return Err(ErrDefine::ArraySizeError);
+        }
+        let body_len = (max_size as usize).checked_mul(13 + T::SIZE).ok_or(ErrDefine::Corrupted)?;
+        if bytes.len() - reader.pos != body_len {
+            return Err(ErrDefine::Corrupted);
+        }
+
+        let in_range = |index: Option<u32>| !matches!(index, Some(index) if index >= max_size);
+        let mut internal_vec = Vec::with_capacity(max_size as usize);
+        for i in 0..max_size {
+            let valid = match reader.take(1)?[0] {
+                0 => false,
+                1 => true,
+                _ => return Err(ErrDefine::Corrupted),
+            };
+            let pre = reader.link()?;
+            let next = reader.link()?;
+            let gen = reader.u32()?;
+            let data = reader.take(T::SIZE)?;
+            if !in_range(pre) || !in_range(next) {
+                return Err(ErrDefine::Corrupted);
+            }
+
+            internal_vec.push(QuickElement {
+                data: if valid { MaybeUninit::new(T::read_bytes(data)) } else { MaybeUninit::uninit() },
+                pre: Link::new(pre),
+                next: Link::new(next),
+                cur: I::from_u32(i),
+                valid,
+                gen,
+            });
+        }
+
+        let new_array = Self {
+            max_size,
+            free_head,
+            valid_head,
+            valid_tail,
+            valid_count,
+            retired_gen: 0,
+            internal_vec,
+            marker: PhantomData,
+        };
+        if new_array.check_integrity().is_err() {
+            return Err(ErrDefine::Corrupted);
+        }
+
+        Ok(new_array)
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>> + Clone> Clone for QuickArray<T, I, S> {
+    fn clone(&self) -> Self {
+        Self {
+            max_size: self.max_size,
+            free_head: self.free_head,
+            valid_head: self.valid_head,
+            valid_tail: self.valid_tail,
+            valid_count: self.valid_count,
+            retired_gen: self.retired_gen,
+            internal_vec: self.internal_vec.clone(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Reuses the allocation of `self` when it is large enough to hold `source`.
+    fn clone_from(&mut self, source: &Self) {
+        self.max_size = source.max_size;
+        self.free_head = source.free_head;
+        self.valid_head = source.valid_head;
+        self.valid_tail = source.valid_tail;
+        self.valid_count = source.valid_count;
+        self.retired_gen = source.retired_gen;
+        self.internal_vec.clone_from(&source.internal_vec);
+    }
+}
+
+/// An empty array of capacity 1, the smallest size `new` produces.
+impl<T: Sized + Copy + Debug, I: IndexType> Default for QuickArray<T, I> {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType> QuickArraySlice<'a, T, I> {
+    /// Builds an empty array over `buffer`, which does not need to be initialized; every slot
+    /// is written before use. Panics if the buffer is empty or too large for the index type.
+    pub fn from_slice_storage(buffer: &'a mut [MaybeUninit<QuickElement<T, I>>]) -> Self {
+        for slot in buffer.iter_mut() {
+            slot.write(QuickElement::default());
+        }
+
+        // SAFETY: every slot was just initialized, and `MaybeUninit<E>` has the layout of `E`
+        let slots = unsafe { &mut *(buffer as *mut [MaybeUninit<QuickElement<T, I>>] as *mut [QuickElement<T, I>]) };
+        Self::with_storage(slots)
+    }
+}
+
+/// An empty array using all `N` slots.
+impl<T: Sized + Copy + Debug, I: IndexType, const N: usize> Default for QuickArrayFixed<T, N, I> {
+    fn default() -> Self {
+        let () = FixedStorage::<QuickElement<T, I>, N>::NOT_EMPTY;
+        Self::with_storage(FixedStorage([QuickElement::default(); N]))
+    }
+}
+
+/// Builds an array with capacity equal to the vec length, holding its items in order. Panics
+/// if the length does not fit the index type; use `QuickArray::try_from_slice` for input that
+/// may be too long.
+impl<T: Sized + Copy + Debug, I: IndexType> From<Vec<T>> for QuickArray<T, I> {
+    fn from(data: Vec<T>) -> Self {
+        Self::from_slice(&data)
+    }
+}
+
+/// Same as `QuickArray::from_slice`.
+impl<T: Sized + Copy + Debug, I: IndexType> From<&[T]> for QuickArray<T, I> {
+    fn from(data: &[T]) -> Self {
+        Self::from_slice(data)
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> From<QuickArray<T, I, S>> for Vec<T> {
+    fn from(array: QuickArray<T, I, S>) -> Self {
+        array.to_vec()
+    }
+}
+
+/// Collects into an array whose capacity equals the number of items. Panics if that count
+/// does not fit the index type.
+impl<T: Sized + Copy + Debug, I: IndexType> FromIterator<T> for QuickArray<T, I> {
+    fn from_iter<Iter: IntoIterator<Item = T>>(iter: Iter) -> Self {
+        let data: Vec<T> = iter.into_iter().collect();
+        Self::from_slice(&data)
+    }
+}
+
+/// Pushes to the back until the array is full; further items are dropped. Use `try_extend` to
+/// learn how many were skipped.
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Extend<T> for QuickArray<T, I, S> {
+    fn extend<Iter: IntoIterator<Item = T>>(&mut self, iter: Iter) {
+        self.try_extend(iter);
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug + 'a, I: IndexType, S: Storage<QuickElement<T, I>>> Extend<&'a T> for QuickArray<T, I, S> {
+    fn extend<Iter: IntoIterator<Item = &'a T>>(&mut self, iter: Iter) {
+        self.try_extend(iter.into_iter().copied());
+    }
+}
+
+/// Lists the valid elements as `(index, value)` pairs in list order. The alternate form
+/// (`{:#?}`) dumps the raw slots and free-list pointers instead.
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Debug for QuickArray<T, I, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.debug_struct("QuickArray")
+                .field("max_size", &self.max_size)
+                .field("free_head", &self.free_head)
+                .field("valid_head", &self.valid_head)
+                .field("valid_tail", &self.valid_tail)
+                .field("valid_count", &self.valid_count)
+                .field("internal_vec", &&self.internal_vec[..])
+                .finish()
+        } else {
+            f.debug_struct("QuickArray")
+                .field("max_size", &self.max_size)
+                .field("valid_count", &self.valid_count)
+                .field("elements", &DebugElements(self))
+                .finish()
+        }
+    }
+}
+
+struct DebugElements<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>>(&'a QuickArray<T, I, S>);
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Debug for DebugElements<'a, T, I, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+/// Renders the values as `[a, b, c]` in list order.
+impl<T: Sized + Copy + Debug + Display, I: IndexType, S: Storage<QuickElement<T, I>>> Display for QuickArray<T, I, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, (_, data)) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            Display::fmt(data, f)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Display adapter returned by `QuickArray::fmt_chain`.
+pub struct ChainFmt<'a, T: Sized + Copy + Debug, I: IndexType = u32, S: Storage<QuickElement<T, I>> = Vec<QuickElement<T, I>>>(&'a QuickArray<T, I, S>);
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Display for ChainFmt<'a, T, I, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "head")?;
+        for (index, data) in self.0.iter() {
+            write!(f, " -> [{}]={:?}", index, data)?;
+        }
+        write!(f, " -> tail ({} free)", self.0.available_capacity())
+    }
+}
+
+impl<T: Sized + Copy + Debug + PartialEq, I: IndexType, S: Storage<QuickElement<T, I>>> PartialEq for QuickArray<T, I, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.valid_count == other.valid_count
+            && self.iter().zip(other.iter()).all(|(a, b)| a.1 == b.1)
+    }
+}
+
+impl<T: Sized + Copy + Debug + Eq, I: IndexType, S: Storage<QuickElement<T, I>>> Eq for QuickArray<T, I, S> {}
+
+impl<T: Sized + Copy + Debug + Hash, I: IndexType, S: Storage<QuickElement<T, I>>> Hash for QuickArray<T, I, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.valid_count.hash(state);
+        for (_, data) in self.iter() {
+            data.hash(state);
+        }
+    }
+}
+
+/// Panics if `index` is out of range or refers to a recycled slot, like indexing a `Vec`
+/// out of bounds. Use `get_element` for the non-panicking lookup.
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Index<u32> for QuickArray<T, I, S> {
+    type Output = T;
+
+    fn index(&self, index: u32) -> &T {
+        match self.get_element(index) {
+            Some(data) => data,
+            None => panic!("index {} is not a valid element of the quick array", index),
+        }
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> IndexMut<u32> for QuickArray<T, I, S> {
+    fn index_mut(&mut self, index: u32) -> &mut T {
+        match self.get_element_mut(index) {
+            Some(data) => data,
+            None => panic!("index {} is not a valid element of the quick array", index),
+        }
+    }
+}
+
+/// Result of `QuickArray::entry`: the slot of the first matching element, or a handle to
+/// insert one.
+pub enum Entry<'a, T: Sized + Copy + Debug, I: IndexType = u32, S: Storage<QuickElement<T, I>> = Vec<QuickElement<T, I>>> {
+    Occupied(u32),
+    Vacant(VacantEntry<'a, T, I, S>),
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Entry<'a, T, I, S> {
+    /// Returns the slot of the existing element, or pushes `data` to the back and returns its
+    /// new slot.
+    pub fn or_insert(self, data: &T) -> Result<u32, ErrDefine> {
+        match self {
+            Entry::Occupied(index) => Ok(index),
+            Entry::Vacant(entry) => entry.insert(data),
+        }
+    }
+}
+
+pub struct VacantEntry<'a, T: Sized + Copy + Debug, I: IndexType = u32, S: Storage<QuickElement<T, I>> = Vec<QuickElement<T, I>>> {
+    array: &'a mut QuickArray<T, I, S>,
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> VacantEntry<'a, T, I, S> {
+    pub fn insert(self, data: &T) -> Result<u32, ErrDefine> {
+        self.array.push_back(data)
+    }
+}
+
+/// Key-to-slot snapshot built by `QuickArray::rebuild_lookup`. Entries are stored as handles,
+/// so an element removed afterwards is no longer found, but elements inserted or updated
+/// afterwards are not picked up until the lookup is rebuilt.
+///
+/// The lookup is kept outside the array rather than as a map inside it, so `QuickArray` does
+/// not carry a key type parameter and its insertions and removals never pay for hashing;
+/// pass the array to `get_by_key` to resolve a key.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct KeyLookup<K: Eq + Hash> {
+    map: std::collections::HashMap<K, Handle>,
+}
+
+#[cfg(feature = "std")]
+impl<K: Eq + Hash> KeyLookup<K> {
+    pub fn get_index(&self, key: &K) -> Option<u32> {
+        self.map.get(key).map(|handle| handle.index)
+    }
+
+    pub fn get_by_key<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>>(&self, array: &'a QuickArray<T, I, S>, key: &K) -> Option<&'a T> {
+        self.map.get(key).and_then(|handle| array.get_by_handle(*handle))
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+type EvictCallback<T> = Box<dyn FnMut(u32, &T)>;
+
+/// A `QuickArray` that reports removed elements to a callback registered with `on_evict`,
+/// e.g. to write evicted cache entries back to disk. Reads go through `Deref`; mutations are
+/// only available through the wrapper methods below, and every one of them that removes
+/// elements reports each removed value, so none of them bypass the callback.
+pub struct EvictingQuickArray<T: Sized + Copy + Debug, I: IndexType = u32> {
+    array: QuickArray<T, I>,
+    on_evict: Option<EvictCallback<T>>,
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType> EvictingQuickArray<T, I> {
+    pub fn new(max_size: u32) -> Self {
+        Self::from_array(QuickArray::new(max_size))
+    }
+
+    pub fn from_array(array: QuickArray<T, I>) -> Self {
+        Self { array, on_evict: None }
+    }
+
+    pub fn into_inner(self) -> QuickArray<T, I> {
+        self.array
+    }
+
+    /// Registers `f` to be called with the slot index and value of every removed element,
+    /// replacing any previous callback.
+    pub fn on_evict<F: FnMut(u32, &T) + 'static>(&mut self, f: F) {
+        self.on_evict = Some(Box::new(f));
+    }
+
+    pub fn push_back(&mut self, data: &T) -> Result<u32, ErrDefine> {
+        self.array.push_back(data)
+    }
+
+    pub fn push_front(&mut self, data: &T) -> Result<u32, ErrDefine> {
+        self.array.push_front(data)
+    }
+
+    pub fn insert_before(&mut self, index: u32, data: &T) -> Result<u32, ErrDefine> {
+        self.array.insert_before(index, data)
+    }
+
+    pub fn insert_after(&mut self, index: u32, data: &T) -> Result<u32, ErrDefine> {
+        self.array.insert_after(index, data)
+    }
+
+    pub fn update_at(&mut self, index: u32, data: &T) -> Result<(), ErrDefine> {
+        self.array.update_at(index, data)
+    }
+
+    pub fn update_with<F: FnOnce(&mut T)>(&mut self, index: u32, f: F) -> Result<(), ErrDefine> {
+        self.array.update_with(index, f)
+    }
+
+    pub fn get_element_mut(&mut self, index: u32) -> Option<&mut T> {
+        self.array.get_element_mut(index)
+    }
+
+    pub fn iter_mut(&mut self) -> QuickArrayIterMut<'_, T, I> {
+        self.array.iter_mut()
+    }
+
+    pub fn move_to_front(&mut self, index: u32) -> Result<(), ErrDefine> {
+        self.array.move_to_front(index)
+    }
+
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, cmp: F) {
+        self.array.sort_by(cmp)
+    }
+
+    pub fn push_front_evicting(&mut self, data: &T) -> (u32, Option<T>) {
+        let tail = self.array.get_tail_index();
+        let (index, evicted) = self.array.push_front_evicting(data);
+        if let (Some(tail), Some(value)) = (tail, evicted.as_ref()) {
+            self.notify(tail, value);
+        }
+
+        (index, evicted)
+    }
+
+    pub fn remove_at(&mut self, index: u32) -> Result<T, ErrDefine> {
+        let data = self.array.remove_at(index)?;
+        self.notify(index, &data);
+
+        Ok(data)
+    }
+
+    pub fn swap_remove(&mut self, index: u32) -> Result<T, ErrDefine> {
+        let data = self.array.swap_remove(index)?;
+        self.notify(index, &data);
+
+        Ok(data)
+    }
+
+    pub fn remove_value(&mut self, value: &T) -> Option<u32>
+    where
+        T: PartialEq,
+    {
+        let index = self.array.remove_value(value)?;
+        self.notify(index, value);
+
+        Some(index)
+    }
+
+    pub fn remove_many(&mut self, indices: &[u32]) -> u32 {
+        let mut count = 0;
+        for index in indices {
+            if self.array.is_valid_index(*index) && self.remove_at(*index).is_ok() {
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.array.get_head_index()?;
+        self.remove_at(head).ok()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.array.get_tail_index()?;
+        self.remove_at(tail).ok()
+    }
+
+    pub fn pop_last(&mut self) -> Result<(), ErrDefine> {
+        let tail = self.array.peek_back().map(|(index, data)| (index, *data));
+        self.array.pop_last()?;
+        if let Some((index, data)) = tail {
+            self.notify(index, &data);
+        }
+
+        Ok(())
+    }
+
+    pub fn remove_range(&mut self, start: u32, count: u32) -> Result<u32, ErrDefine> {
+        let removed: Vec<(u32, T)> = self
+            .array
+            .iter()
+            .skip(start as usize)
+            .take(count as usize)
+            .map(|(index, data)| (index, *data))
+            .collect();
+        let count = self.array.remove_range(start, count)?;
+        for (index, data) in &removed {
+            self.notify(*index, data);
+        }
+
+        Ok(count)
+    }
+
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut removed = Vec::new();
+        self.array.retain_visit(|index, data| {
+            let keep = f(data);
+            if !keep {
+                removed.push((index, *data));
+            }
+            keep
+        });
+        for (index, data) in &removed {
+            self.notify(*index, data);
+        }
+    }
+
+    pub fn drain_while<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Vec<T> {
+        let mut result = Vec::new();
+        while let Some((head, data)) = self.array.peek_front() {
+            if !pred(data) {
+                break;
+            }
+            result.push(self.remove_at(head).expect("head slot must be removable"));
+        }
+
+        result
+    }
+
+    /// Removes elements from the back until at most `len` remain, reporting each one.
+    pub fn truncate(&mut self, len: u32) {
+        while self.array.len() > len as usize {
+            self.pop_back();
+        }
+    }
+
+    /// Removes every element, reporting them in list order.
+    pub fn clear(&mut self) {
+        let removed: Vec<(u32, T)> = self.array.iter().map(|(index, data)| (index, *data)).collect();
+        self.array.clear();
+        for (index, data) in &removed {
+            self.notify(*index, data);
+        }
+    }
+
+    fn notify(&mut self, index: u32, data: &T) {
+        if let Some(f) = self.on_evict.as_mut() {
+            f(index, data);
+        }
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType> Deref for EvictingQuickArray<T, I> {
+    type Target = QuickArray<T, I>;
+
+    fn deref(&self) -> &QuickArray<T, I> {
+        &self.array
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType> Debug for EvictingQuickArray<T, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EvictingQuickArray")
+            .field("array", &self.array)
+            .field("on_evict", &self.on_evict.is_some())
+            .finish()
+    }
+}
+
+pub struct QuickArrayIterator<'a, T: Sized + Copy + Debug, I: IndexType = u32, S: Storage<QuickElement<T, I>> = Vec<QuickElement<T, I>>> {
+    pub array : &'a QuickArray<T, I, S>,
+    pub index: Option<u32>,
+    pub back: Option<u32>,
+    pub remaining: u32,
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Iterator for QuickArrayIterator<'a, T, I, S> {
+    type Item = (u32, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let cur_index = self.index?;
+        let cur_ele = self.array.get_element(cur_index)?;
+        self.index = self.array.get_next_index(cur_index);
+        self.remaining -= 1;
+        Some((cur_index, cur_ele))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> DoubleEndedIterator for QuickArrayIterator<'a, T, I, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let cur_index = self.back?;
+        let cur_ele = self.array.get_element(cur_index)?;
+        self.back = self.array.get_pre_index(cur_index);
+        self.remaining -= 1;
+        Some((cur_index, cur_ele))
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> ExactSizeIterator for QuickArrayIterator<'a, T, I, S> {}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> FusedIterator for QuickArrayIterator<'a, T, I, S> {}
+
+/// Owning iterator over the values of a `QuickArray` in list order.
+pub struct QuickArrayIntoIter<T: Sized + Copy + Debug, I: IndexType = u32, S: Storage<QuickElement<T, I>> = Vec<QuickElement<T, I>>> {
+    array: QuickArray<T, I, S>,
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Iterator for QuickArrayIntoIter<T, I, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.array.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.array.valid_count as usize, Some(self.array.valid_count as usize))
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> DoubleEndedIterator for QuickArrayIntoIter<T, I, S> {
+    fn next_back(&mut self) -> Option<T> {
+        self.array.pop_back()
+    }
+}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> ExactSizeIterator for QuickArrayIntoIter<T, I, S> {}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> FusedIterator for QuickArrayIntoIter<T, I, S> {}
+
+impl<T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> IntoIterator for QuickArray<T, I, S> {
+    type Item = T;
+    type IntoIter = QuickArrayIntoIter<T, I, S>;
+
+    fn into_iter(self) -> QuickArrayIntoIter<T, I, S> {
+        QuickArrayIntoIter { array: self }
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> IntoIterator for &'a QuickArray<T, I, S> {
+    type Item = (u32, &'a T);
+    type IntoIter = QuickArrayIterator<'a, T, I, S>;
+
+    fn into_iter(self) -> QuickArrayIterator<'a, T, I, S> {
+        self.iter()
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> IntoIterator for &'a mut QuickArray<T, I, S> {
+    type Item = (u32, &'a mut T);
+    type IntoIter = QuickArrayIterMut<'a, T, I>;
+
+    fn into_iter(self) -> QuickArrayIterMut<'a, T, I> {
+        self.iter_mut()
+    }
+}
+
+/// Draining iterator created by `QuickArray::drain`. Yields the values in list order; when
+/// dropped, the array is cleared even if the iterator was not exhausted.
+pub struct QuickArrayDrain<'a, T: Sized + Copy + Debug, I: IndexType = u32, S: Storage<QuickElement<T, I>> = Vec<QuickElement<T, I>>> {
+    array: &'a mut QuickArray<T, I, S>,
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Iterator for QuickArrayDrain<'a, T, I, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.array.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.array.valid_count as usize, Some(self.array.valid_count as usize))
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> DoubleEndedIterator for QuickArrayDrain<'a, T, I, S> {
+    fn next_back(&mut self) -> Option<T> {
+        self.array.pop_back()
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> ExactSizeIterator for QuickArrayDrain<'a, T, I, S> {}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> FusedIterator for QuickArrayDrain<'a, T, I, S> {}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, S: Storage<QuickElement<T, I>>> Drop for QuickArrayDrain<'a, T, I, S> {
+    fn drop(&mut self) {
+        self.array.clear();
+    }
+}
+
+/// Iterator created by `QuickArray::extract_if`. Elements not reached before it is dropped
+/// stay in the array.
+pub struct QuickArrayExtractIf<'a, T: Sized + Copy + Debug, I: IndexType, F: FnMut(&T) -> bool, S: Storage<QuickElement<T, I>> = Vec<QuickElement<T, I>>> {
+    array: &'a mut QuickArray<T, I, S>,
+    index: Option<u32>,
+    pred: F,
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, F: FnMut(&T) -> bool, S: Storage<QuickElement<T, I>>> Iterator for QuickArrayExtractIf<'a, T, I, F, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while let Some(cur_index) = self.index {
+            let e = &self.array.internal_vec[cur_index as usize];
+            self.index = e.next.get();
+            if (self.pred)(e.data()) {
+                return self.array.remove_at(cur_index).ok();
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType, F: FnMut(&T) -> bool, S: Storage<QuickElement<T, I>>> FusedIterator for QuickArrayExtractIf<'a, T, I, F, S> {}
+
+/// Mutable counterpart of `QuickArrayIterator`, created by `QuickArray::iter_mut`.
+pub struct QuickArrayIterMut<'a, T: Sized + Copy + Debug, I: IndexType = u32> {
+    elements: *mut QuickElement<T, I>,
+    index: Option<u32>,
+    remaining: u32,
+    marker: PhantomData<&'a mut QuickElement<T, I>>,
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType> Iterator for QuickArrayIterMut<'a, T, I> {
+    type Item = (u32, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cur_index = self.index?;
+        // SAFETY: the iterator borrows the array mutably for 'a and walks the valid list, which
+        // visits every slot at most once, so the yielded references never alias
+        let e = unsafe { &mut *self.elements.add(cur_index as usize) };
+        self.index = e.next.get();
+        self.remaining -= 1;
+        Some((cur_index, e.data_mut()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType> ExactSizeIterator for QuickArrayIterMut<'a, T, I> {}
+
+impl<'a, T: Sized + Copy + Debug, I: IndexType> FusedIterator for QuickArrayIterMut<'a, T, I> {}
+
+// SAFETY: the iterator behaves like the `&'a mut [QuickElement<T, I>]` it was created from
+unsafe impl<'a, T: Sized + Copy + Debug + Send, I: IndexType> Send for QuickArrayIterMut<'a, T, I> {}
+
+// SAFETY: see `Send`; a shared iterator gives no access to the elements
+unsafe impl<'a, T: Sized + Copy + Debug + Sync, I: IndexType> Sync for QuickArrayIterMut<'a, T, I> {}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::borrow::Borrow;
+
+    fn display_array<T: Sized + Copy + Debug>(array: &QuickArray<T>) {
+        println!("{:?}", array);
+        println!("=================================================");
+    }
+
+    #[test]
+    fn it_works() {
+        println!("array with 1 element init");
+        let _test_array = QuickArray::<u32>::new(1);
+
+        println!("array init");
+        let mut test_array = QuickArray::<u32>::new(5);
+        display_array(&test_array);
+
+        println!("array push 111");
+        let _result: Result<u32, ErrDefine> = test_array.push_back(111_u32.borrow());
+        display_array(&test_array);
+
+        println!("array insert 222 after 0");
+        let _result: Result<u32, ErrDefine> = test_array.insert_after(0, 222_u32.borrow());
+        display_array(&test_array);
+
+        println!("array insert 333 after 0");
+        let _result: Result<u32, ErrDefine> = test_array.insert_after(0, 333_u32.borrow());
+        display_array(&test_array);
+
+        println!("array remove at 1");
+        let _result: Result<u32, ErrDefine> = test_array.remove_at(1);
+        display_array(&test_array);
+
+        println!("array pop last");
+        let _result: Result<(), ErrDefine> = test_array.pop_last();
+        display_array(&test_array);
+
+        println!("array pop last");
+        let _result: Result<(), ErrDefine> = test_array.pop_last();
+        display_array(&test_array);
+
+        println!("array pop last");
+        let result: Result<(), ErrDefine> = test_array.pop_last();
+        display_array(&test_array);
+        if let Err(ErrDefine::ArrayIsEmpty) = result {
+            println!("Array is empty")
+        }
+
+        println!("array push 4 numbers");
+        let _result: Result<u32, ErrDefine> = test_array.push_back(444_u32.borrow());
+        display_array(&test_array);
+
+        let _result: Result<u32, ErrDefine> = test_array.push_front(4444_u32.borrow());
+        display_array(&test_array);
+
+        let _result: Result<u32, ErrDefine> = test_array.push_back(44444_u32.borrow());
+        display_array(&test_array);
+
+        let _result: Result<u32, ErrDefine> = test_array.push_back(444444_u32.borrow());
+        display_array(&test_array);
+
+        let _result: Result<u32, ErrDefine> = test_array.push_back(4444444_u32.borrow());
+        display_array(&test_array);
+
+        println!("array push 6 111");
+        let result: Result<u32, ErrDefine> = test_array.push_back(111_u32.borrow());
+        display_array(&test_array);
+        if let Err(ErrDefine::ArrayIsFull) = result {
+            println!("Array is full")
+        }
+
+        println!("array update 999 at 0");
+        let _result = test_array.update_at(0, 999_u32.borrow());
+        display_array(&test_array);
+
+        let ele = test_array.get_head_element();
+        println!("head value is {}", ele.unwrap());
+
+        let ele = test_array.get_tail_element();
+        println!("tail value is {}", ele.unwrap());
+        display_array(&test_array);
+
+        println!("expand array to 10");
+        let _result = test_array.expand_to(10);
+        display_array(&test_array);
+
+        let _result: Result<u32, ErrDefine> = test_array.push_back(888_u32.borrow());
+        display_array(&test_array);
+
+        let _result: Result<(), ErrDefine> = test_array.pop_last();
+        display_array(&test_array);
+
+        let _result = test_array.push_front(666_u32.borrow());
+        display_array(&test_array);
+
+        for (i, e) in test_array.iter() {
+            println!("{}:{}", i, e)
+        }
+    }
+
+    #[test]
+    fn test_lru(){
+        const LRU_LEN:u32=3;
+        let mut array_obj= QuickArray::<i32>::new(LRU_LEN);
+        let mut push_fn=|val: i32|{
+            if array_obj.is_full(){
+                {
+                    let last_item= array_obj.get_tail_element().unwrap();
+                    assert_eq!(*last_item, val-(LRU_LEN as i32));
+                }
+                array_obj.pop_last().expect("pop last error");
+            }
+
+            array_obj.push_front(&val).expect("push error");
+            if val>=LRU_LEN as i32{
+                assert_eq!( array_obj.len(),LRU_LEN as usize);
+            }else{
+                assert_eq!( array_obj.len(),val as usize);
+            }
+        };
+
+        push_fn(1);
+        push_fn(2);
+        push_fn(3);
+        push_fn(4);
+        push_fn(5);
+        push_fn(6);
+        push_fn(7);
+        push_fn(8);
+        push_fn(9);
+        push_fn(10);
+    }
+
+    #[test]
+    fn test_normal(){
+        const LRU_LEN:u32=5;
+        // 缓存未满的情况
+        let total_data=vec![1,2];
+        let mut array_obj= QuickArray::<i32>::new(LRU_LEN);
+        for item in &total_data{
+            array_obj.push_front(item).expect("push error");
+        }
+        let array_val:Vec<i32>= array_obj.iter().map(|item|*item.1) .collect();
+        assert_eq!(total_data.len(),array_val.len());
+        assert_eq!(total_data.len(),array_obj.len());
+        for index in 0..array_val.len(){
+            assert_eq!(array_val[index],total_data[total_data.len()-index-1])
+        }
+
+        // 清空测试
+        array_obj.clear();
+        assert_eq!(array_obj.len(),0);
+        let array_val:Vec<i32>= array_obj.iter().map(|item|*item.1) .collect();
+        assert_eq!(array_val.len(),0);
+
+        // 链满的情况
+        let total_data=vec![1,2,3,4,5,6,7,8,9];
         for item in &total_data{
             if array_obj.is_full(){
                 array_obj.pop_last().expect("pop error");
             }
-            array_obj.push_front(item).expect("push error");
+            array_obj.push_front(item).expect("push error");
+        }
+        let array_val:Vec<i32>= array_obj.iter().map(|item|*item.1) .collect();
+        assert_eq!(array_val.len(),LRU_LEN as usize);
+        assert_eq!(array_obj.len(),LRU_LEN as usize);
+        for index in 0..array_val.len(){
+            assert_eq!(array_val[index],total_data[total_data.len()-index-1])
+        }
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        let a = array_obj.push_back(&1).unwrap();
+        let b = array_obj.push_back(&2).unwrap();
+        array_obj.push_back(&3).unwrap();
+
+        array_obj.swap(a, b).expect("swap error");
+        let array_val: Vec<i32> = array_obj.iter().map(|item| *item.1).collect();
+        assert_eq!(array_val, vec![2, 1, 3]);
+        assert_eq!(array_obj.get_head_index(), Some(a));
+
+        array_obj.swap(a, a).expect("self swap error");
+        assert_eq!(*array_obj.get_element(a).unwrap(), 2);
+
+        assert!(matches!(array_obj.swap(a, 3), Err(ErrDefine::InvalidIndex)));
+        assert!(matches!(array_obj.swap(a, 4), Err(ErrDefine::InvalidIndex)));
+    }
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(ErrDefine::ArrayIsFull.to_string(), "array is full");
+        assert_eq!(ErrDefine::Corrupted.to_string(), "array links are corrupted");
+        #[cfg(feature = "std")]
+        {
+            let err: Box<dyn std::error::Error> = Box::new(ErrDefine::InvalidIndex);
+            assert_eq!(err.to_string(), "invalid index");
+        }
+    }
+
+    #[test]
+    fn test_index_type() {
+        assert!(std::mem::size_of::<QuickElement<u8, u16>>() < std::mem::size_of::<QuickElement<u8, u32>>());
+
+        let mut array_obj = QuickArray::<i32, u16>::new(3);
+        let first = array_obj.push_back(&1).unwrap();
+        array_obj.push_back(&3).unwrap();
+        array_obj.insert_after(first, &2).unwrap();
+        assert!(matches!(array_obj.push_front(&0), Err(ErrDefine::ArrayIsFull)));
+        let array_val: Vec<i32> = array_obj.iter().map(|item| *item.1).collect();
+        assert_eq!(array_val, vec![1, 2, 3]);
+
+        array_obj.remove_at(first).unwrap();
+        assert_eq!(array_obj.get_pre_index(array_obj.get_head_index().unwrap()), None);
+        array_obj.expand_to(6).unwrap();
+        assert_eq!(array_obj.capacity(), 6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_type_too_large() {
+        QuickArray::<i32, u16>::new(u16::MAX as u32);
+    }
+
+    #[test]
+    fn test_expand_to() {
+        // expand a full array
+        let mut array_obj = QuickArray::<i32>::new(5);
+        for i in 0..5 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        array_obj.expand_to(10).expect("expand error");
+        assert_eq!(array_obj.capacity(), 10);
+        for i in 5..10 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        assert!(array_obj.is_full());
+        assert!(matches!(array_obj.push_back(&10), Err(ErrDefine::ArrayIsFull)));
+        let array_val: Vec<i32> = array_obj.iter().map(|item| *item.1).collect();
+        assert_eq!(array_val, (0..10).collect::<Vec<i32>>());
+
+        // expand a partially filled array, old free slots must stay reachable
+        let mut array_obj = QuickArray::<i32>::new(4);
+        array_obj.push_back(&0).expect("push error");
+        array_obj.push_back(&1).expect("push error");
+        array_obj.expand_to(5).expect("expand error");
+        let mut used = vec![0, 1];
+        for i in 2..5 {
+            used.push(array_obj.push_back(&i).expect("push error"));
+        }
+        used.sort_unstable();
+        assert_eq!(used, vec![0, 1, 2, 3, 4]);
+        assert!(array_obj.is_full());
+
+        assert!(matches!(array_obj.expand_to(5), Err(ErrDefine::ArraySizeError)));
+    }
+
+    #[test]
+    fn test_new_zero_size() {
+        let mut array_obj = QuickArray::<i32>::new(0);
+        assert_eq!(array_obj.capacity(), 1);
+        assert!(array_obj.is_empty());
+        array_obj.push_back(&7).expect("push error");
+        assert_eq!(array_obj.get_head_element(), Some(&7));
+        assert!(matches!(array_obj.push_back(&8), Err(ErrDefine::ArrayIsFull)));
+    }
+
+    #[test]
+    fn test_push_front_evicting() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        for i in 1..=3 {
+            let (_, evicted) = array_obj.push_front_evicting(&i);
+            assert_eq!(evicted, None);
+        }
+        assert!(array_obj.is_full());
+
+        let (index, evicted) = array_obj.push_front_evicting(&4);
+        assert_eq!(evicted, Some(1));
+        assert_eq!(array_obj.get_head_index(), Some(index));
+        assert_eq!(array_obj.len(), 3);
+        let array_val: Vec<i32> = array_obj.iter().map(|item| *item.1).collect();
+        assert_eq!(array_val, vec![4, 3, 2]);
+    }
+
+    #[test]
+    fn test_find() {
+        let mut array_obj = QuickArray::<(u32, i32)>::new(4);
+        array_obj.push_back(&(1, 10)).expect("push error");
+        let second = array_obj.push_back(&(2, 20)).expect("push error");
+        array_obj.push_back(&(2, 30)).expect("push error");
+        let front = array_obj.push_front(&(3, 40)).expect("push error");
+
+        assert_eq!(array_obj.find(|item| item.0 == 2), Some(second));
+        assert_eq!(array_obj.find(|item| item.0 == 3), Some(front));
+        assert_eq!(array_obj.find(|item| item.0 == 4), None);
+        assert!(array_obj.contains(&(2, 30)));
+        assert!(!array_obj.contains(&(2, 40)));
+
+        array_obj.remove_at(second).expect("remove error");
+        assert_ne!(array_obj.find(|item| item.0 == 2), Some(second));
+        assert!(!array_obj.contains(&(2, 20)));
+    }
+
+    #[test]
+    fn test_index() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        assert_eq!(array_obj[a], 1);
+        assert_eq!(array_obj[b], 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_recycled_slot() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        let a = array_obj.push_back(&1).expect("push error");
+        array_obj.remove_at(a).expect("remove error");
+        let _ = array_obj[a];
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_range() {
+        let array_obj = QuickArray::<i32>::new(3);
+        let _ = array_obj[3];
+    }
+
+    #[test]
+    fn test_from_slice() {
+        let array_obj = QuickArray::<i32>::from_slice(&[3, 1, 2]);
+        assert_eq!(array_obj.capacity(), 3);
+        assert!(array_obj.is_full());
+        let array_val: Vec<i32> = array_obj.iter().map(|item| *item.1).collect();
+        assert_eq!(array_val, vec![3, 1, 2]);
+
+        let array_obj = QuickArray::<i32>::from_slice(&[]);
+        assert_eq!(array_obj.capacity(), 1);
+        assert!(array_obj.is_empty());
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert!(array_obj.to_vec().is_empty());
+        array_obj.push_back(&2).expect("push error");
+        let first = array_obj.push_front(&1).expect("push error");
+        array_obj.push_back(&3).expect("push error");
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3]);
+        array_obj.remove_at(first).expect("remove error");
+        assert_eq!(array_obj.to_vec(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        let first = array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        array_obj.push_front(&0).expect("push error");
+
+        let cloned = array_obj.clone();
+        array_obj.remove_at(first).expect("remove error");
+        array_obj.update_at(array_obj.get_head_index().unwrap(), &9).expect("update error");
+        array_obj.push_back(&3).expect("push error");
+
+        assert_eq!(array_obj.to_vec(), vec![9, 2, 3]);
+        assert_eq!(cloned.to_vec(), vec![0, 1, 2]);
+        assert_eq!(cloned.len(), 3);
+        assert_eq!(cloned.get_element(first), Some(&1));
+        let cloned_val: Vec<(u32, i32)> = cloned.iter().map(|item| (item.0, *item.1)).collect();
+        assert_eq!(cloned_val.len(), 3);
+    }
+
+    #[test]
+    fn test_eq() {
+        let mut array_a = QuickArray::<i32>::new(4);
+        array_a.push_back(&1).expect("push error");
+        array_a.push_back(&2).expect("push error");
+        array_a.push_back(&3).expect("push error");
+
+        let mut array_b = QuickArray::<i32>::new(6);
+        let tmp = array_b.push_front(&3).expect("push error");
+        array_b.push_front(&9).expect("push error");
+        array_b.insert_before(tmp, &2).expect("insert error");
+        array_b.pop_last().expect("pop error");
+        array_b.push_back(&3).expect("push error");
+        let head = array_b.get_head_index().unwrap();
+        array_b.remove_at(head).expect("remove error");
+        array_b.push_front(&1).expect("push error");
+
+        assert_eq!(array_a, array_b);
+
+        array_b.update_at(array_b.get_tail_index().unwrap(), &4).expect("update error");
+        assert_ne!(array_a, array_b);
+        array_b.pop_last().expect("pop error");
+        assert_ne!(array_a, array_b);
+        assert_eq!(QuickArray::<i32>::new(1), QuickArray::<i32>::new(5));
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        array_obj.push_back(&1).expect("push error");
+        let removed = array_obj.push_back(&2).expect("push error");
+        array_obj.push_front(&3).expect("push error");
+        array_obj.remove_at(removed).expect("remove error");
+
+        assert_eq!(
+            format!("{:?}", array_obj),
+            "QuickArray { max_size: 4, valid_count: 2, elements: [(2, 3), (0, 1)] }"
+        );
+        let raw = format!("{:#?}", array_obj);
+        assert!(raw.contains("free_head"));
+        assert!(raw.contains("internal_vec"));
+    }
+
+    #[test]
+    fn test_free_indices() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert_eq!(array_obj.available_capacity(), 4);
+        assert_eq!(array_obj.free_indices().collect::<Vec<u32>>(), vec![0, 1, 2, 3]);
+
+        let a = array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        array_obj.push_back(&3).expect("push error");
+        assert_eq!(array_obj.available_capacity(), 1);
+        assert_eq!(array_obj.free_indices().collect::<Vec<u32>>(), vec![3]);
+
+        array_obj.remove_at(a).expect("remove error");
+        assert_eq!(array_obj.available_capacity(), 2);
+        assert_eq!(array_obj.free_indices().collect::<Vec<u32>>(), vec![a, 3]);
+
+        array_obj.push_back(&4).expect("push error");
+        array_obj.push_back(&5).expect("push error");
+        assert_eq!(array_obj.available_capacity(), 0);
+        assert_eq!(array_obj.free_indices().count(), 0);
+    }
+
+    #[test]
+    fn test_insert_sorted() {
+        let mut array_obj = QuickArray::<(i32, char)>::new(6);
+        for item in &[(5, 'a'), (1, 'b'), (3, 'c'), (5, 'd'), (0, 'e')] {
+            array_obj.insert_sorted(item, |a, b| a.0.cmp(&b.0)).expect("insert error");
+        }
+        assert_eq!(array_obj.to_vec(), vec![(0, 'e'), (1, 'b'), (3, 'c'), (5, 'a'), (5, 'd')]);
+
+        let last = array_obj.insert_sorted(&(9, 'f'), |a, b| a.0.cmp(&b.0)).expect("insert error");
+        assert_eq!(array_obj.get_tail_index(), Some(last));
+        assert!(matches!(
+            array_obj.insert_sorted(&(2, 'g'), |a, b| a.0.cmp(&b.0)),
+            Err(ErrDefine::ArrayIsFull)
+        ));
+    }
+
+    #[test]
+    fn test_position() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        let b = array_obj.push_back(&20).expect("push error");
+        let a = array_obj.push_front(&10).expect("push error");
+        let c = array_obj.push_back(&30).expect("push error");
+
+        assert_eq!(array_obj.get_by_position(0), Some((a, &10)));
+        assert_eq!(array_obj.get_by_position(1), Some((b, &20)));
+        assert_eq!(array_obj.get_by_position(2), Some((c, &30)));
+        assert_eq!(array_obj.get_by_position(3), None);
+
+        assert_eq!(array_obj.position_of(a), Some(0));
+        assert_eq!(array_obj.position_of(c), Some(2));
+        array_obj.remove_at(b).expect("remove error");
+        assert_eq!(array_obj.position_of(b), None);
+        assert_eq!(array_obj.position_of(c), Some(1));
+        assert_eq!(array_obj.position_of(5), None);
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let total_data = vec![5, 3, 8, 1, 9, 2, 7];
+        let mut array_obj = QuickArray::<i32>::new(8);
+        let mut slots = Vec::new();
+        for item in &total_data {
+            slots.push(array_obj.push_back(item).expect("push error"));
+        }
+
+        array_obj.sort_by(|a, b| a.cmp(b));
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3, 5, 7, 8, 9]);
+        for (slot, item) in slots.iter().zip(total_data.iter()) {
+            assert_eq!(array_obj.get_element(*slot), Some(item));
+        }
+        assert_eq!(array_obj.get_head_element(), Some(&1));
+        assert_eq!(array_obj.get_tail_element(), Some(&9));
+
+        array_obj.sort_by(|a, b| b.cmp(a));
+        assert_eq!(array_obj.to_vec(), vec![9, 8, 7, 5, 3, 2, 1]);
+        array_obj.pop_last().expect("pop error");
+        array_obj.push_front(&0).expect("push error");
+        array_obj.push_back(&4).expect("push error");
+        assert_eq!(array_obj.to_vec(), vec![0, 9, 8, 7, 5, 3, 2, 4]);
+
+        let mut empty = QuickArray::<i32>::new(2);
+        empty.sort_by(|a, b| a.cmp(b));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut array_obj = QuickArray::<i32>::new(2);
+        array_obj.push_back(&0).expect("push error");
+        array_obj.reserve(3);
+        assert_eq!(array_obj.capacity(), 5);
+        assert_eq!(array_obj.available_capacity(), 4);
+        for i in 1..5 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        assert!(array_obj.is_full());
+        assert_eq!(array_obj.to_vec(), vec![0, 1, 2, 3, 4]);
+
+        array_obj.reserve(0);
+        assert_eq!(array_obj.capacity(), 5);
+        array_obj.reserve(1);
+        array_obj.push_front(&-1).expect("push error");
+        assert_eq!(array_obj.to_vec(), vec![-1, 0, 1, 2, 3, 4]);
+
+        // growing one slot at a time only resizes the buffer a logarithmic number of times
+        let mut resizes = 0;
+        for i in 0..1000 {
+            let backing = array_obj.backing_capacity();
+            array_obj.reserve(1);
+            array_obj.push_back(&i).expect("push error");
+            if array_obj.backing_capacity() != backing {
+                resizes += 1;
+            }
+        }
+        assert!(resizes <= 12, "{} resizes", resizes);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_reserve_too_large() {
+        let mut array_obj = QuickArray::<i32, u16>::new(2);
+        array_obj.reserve(u16::MAX as u32);
+    }
+
+    #[test]
+    fn test_shrink_to() {
+        let mut array_obj = QuickArray::<i32>::new(8);
+        let mut slots = Vec::new();
+        for i in 0..6 {
+            slots.push(array_obj.push_back(&i).expect("push error"));
+        }
+        array_obj.remove_at(slots[0]).expect("remove error");
+        array_obj.remove_at(slots[3]).expect("remove error");
+        array_obj.push_front(&9).expect("push error");
+
+        assert!(matches!(array_obj.shrink_to(4), Err(ErrDefine::ArraySizeError)));
+        assert!(matches!(array_obj.shrink_to(8), Err(ErrDefine::ArraySizeError)));
+
+        array_obj.shrink_to(6).expect("shrink error");
+        assert_eq!(array_obj.capacity(), 6);
+        assert_eq!(array_obj.to_vec(), vec![9, 1, 2, 4, 5]);
+        assert_eq!(array_obj.iter().map(|item| item.0).collect::<Vec<u32>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(array_obj.get_element(5), None);
+
+        array_obj.push_back(&6).expect("push error");
+        assert!(array_obj.is_full());
+        assert!(matches!(array_obj.push_back(&7), Err(ErrDefine::ArrayIsFull)));
+
+        let mut empty = QuickArray::<i32>::new(4);
+        assert!(matches!(empty.shrink_to(0), Err(ErrDefine::ArraySizeError)));
+        empty.shrink_to(1).expect("shrink error");
+        empty.push_back(&1).expect("push error");
+        assert_eq!(empty.to_vec(), vec![1]);
+    }
+
+    #[test]
+    fn test_clear_invalidates_slots() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        let a = array_obj.push_back(&1).expect("push error");
+        array_obj.clear();
+        assert_eq!(array_obj.get_element(a), None);
+        assert_eq!(array_obj.free_indices().count(), 3);
+    }
+
+    #[test]
+    fn test_handle() {
+        let mut array_obj = QuickArray::<i32>::new(2);
+        let a = array_obj.push_back_handle(&1).expect("push error");
+        let b = array_obj.push_front_handle(&2).expect("push error");
+        assert_eq!(array_obj.get_by_handle(a), Some(&1));
+        assert_eq!(array_obj.get_by_handle(b), Some(&2));
+        assert_eq!(array_obj.get_handle(a.index), Some(a));
+
+        // the recycled slot is reused by a new element, the old handle must not see it
+        array_obj.remove_at(a.index).expect("remove error");
+        assert_eq!(array_obj.get_by_handle(a), None);
+        let c = array_obj.insert_after_handle(b.index, &3).expect("insert error");
+        assert_eq!(c.index, a.index);
+        assert_ne!(c, a);
+        assert_eq!(array_obj.get_element(a.index), Some(&3));
+        assert_eq!(array_obj.get_by_handle(a), None);
+        assert_eq!(array_obj.get_by_handle(c), Some(&3));
+
+        array_obj.clear();
+        assert_eq!(array_obj.get_by_handle(b), None);
+        assert_eq!(array_obj.get_handle(b.index), None);
+        let d = array_obj.push_back_handle(&4).expect("push error");
+        let e = array_obj.insert_before_handle(d.index, &5).expect("insert error");
+        assert_eq!(array_obj.get_by_handle(e), Some(&5));
+        assert_eq!(array_obj.get_by_handle(Handle { index: 7, gen: 0 }), None);
+
+        // slots dropped by a shrink come back with a newer generation when regrown
+        let mut array_obj = QuickArray::<i32>::new(8);
+        for i in 0..8 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        let stale = array_obj.get_handle(7).expect("handle error");
+        array_obj.remove_range(2, 6).expect("remove error");
+        array_obj.shrink_to(2).expect("shrink error");
+        array_obj.expand_to(8).expect("expand error");
+        for i in 100..106 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        assert!(array_obj.is_valid_index(stale.index));
+        assert_eq!(array_obj.get_by_handle(stale), None);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_check_integrity() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        array_obj.check_integrity().expect("integrity error");
+        let a = array_obj.push_back(&1).expect("push error");
+        array_obj.push_front(&2).expect("push error");
+        array_obj.insert_after(a, &3).expect("insert error");
+        array_obj.check_integrity().expect("integrity error");
+        array_obj.remove_at(a).expect("remove error");
+        array_obj.expand_to(8).expect("expand error");
+        array_obj.check_integrity().expect("integrity error");
+        array_obj.sort_by(|a, b| a.cmp(b));
+        array_obj.shrink_to(3).expect("shrink error");
+        array_obj.check_integrity().expect("integrity error");
+
+        let mut broken = array_obj.clone();
+        broken.valid_count += 1;
+        assert!(broken.check_integrity().is_err());
+
+        let mut broken = array_obj.clone();
+        broken.internal_vec[1].cur = 2;
+        assert!(broken.check_integrity().unwrap_err().contains("cur"));
+
+        let mut broken = array_obj.clone();
+        let head = broken.valid_head.expect("array is not empty") as usize;
+        broken.internal_vec[head].next = Link::new(broken.valid_head);
+        assert!(broken.check_integrity().is_err());
+    }
+
+    #[test]
+    fn test_extend_from() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        array_obj.push_back(&0).expect("push error");
+        assert_eq!(array_obj.extend_from(vec![1, 2]).expect("extend error"), 2);
+        assert_eq!(array_obj.extend_from(core::iter::empty()).expect("extend error"), 0);
+        assert!(matches!(array_obj.extend_from(3..10), Err(ErrDefine::ArrayIsFull)));
+        assert_eq!(array_obj.to_vec(), vec![0, 1, 2, 3, 4]);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut array_obj = QuickArray::<i32>::from_slice(&[1, 2, 3, 4, 5]);
+        let other = array_obj.split_off(2).expect("split error");
+        assert_eq!(array_obj.to_vec(), vec![1, 2]);
+        assert_eq!(other.to_vec(), vec![3, 4, 5]);
+        assert_eq!(other.capacity(), 5);
+        array_obj.check_integrity().expect("integrity error");
+        other.check_integrity().expect("integrity error");
+
+        let tail = array_obj.split_off(2).expect("split error");
+        assert!(tail.is_empty());
+        let all = array_obj.split_off(0).expect("split error");
+        assert!(array_obj.is_empty());
+        assert_eq!(all.to_vec(), vec![1, 2]);
+        assert!(matches!(array_obj.split_off(1), Err(ErrDefine::InvalidIndex)));
+    }
+
+    #[test]
+    fn test_append() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        array_obj.push_back(&1).expect("push error");
+        let mut other = QuickArray::<i32>::from_slice(&[2, 3]);
+        array_obj.append(&mut other).expect("append error");
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3]);
+        assert!(other.is_empty());
+
+        let mut too_many = QuickArray::<i32>::from_slice(&[4, 5, 6]);
+        assert!(matches!(array_obj.append(&mut too_many), Err(ErrDefine::ArrayIsFull)));
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3]);
+        assert_eq!(too_many.to_vec(), vec![4, 5, 6]);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_rotate_left() {
+        let mut array_obj = QuickArray::<i32>::from_slice(&[1, 2, 3, 4, 5]);
+        let slot_of_one = array_obj.get_head_index().unwrap();
+        array_obj.rotate_left(2);
+        assert_eq!(array_obj.to_vec(), vec![3, 4, 5, 1, 2]);
+        assert_eq!(array_obj.get_element(slot_of_one), Some(&1));
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.rotate_left(5);
+        assert_eq!(array_obj.to_vec(), vec![3, 4, 5, 1, 2]);
+        array_obj.rotate_left(14);
+        assert_eq!(array_obj.to_vec(), vec![2, 3, 4, 5, 1]);
+        array_obj.check_integrity().expect("integrity error");
+
+        let mut single = QuickArray::<i32>::from_slice(&[1]);
+        single.rotate_left(3);
+        assert_eq!(single.to_vec(), vec![1]);
+        let mut empty = QuickArray::<i32>::new(2);
+        empty.rotate_left(1);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_iterator_len() {
+        let mut array_obj = QuickArray::<i32>::from_slice(&[1, 2, 3]);
+        let mut iter = array_obj.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        iter.next();
+        assert_eq!(iter.len(), 2);
+        iter.next();
+        iter.next();
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.len(), 0);
+
+        array_obj.pop_last().expect("pop error");
+        assert_eq!(array_obj.iter().len(), 2);
+        array_obj.clear();
+        assert_eq!(array_obj.iter().len(), 0);
+    }
+
+    #[test]
+    fn test_push_front_relink() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        for round in 0..3 {
+            let c = array_obj.push_front(&3).expect("push error");
+            let b = array_obj.push_front(&2).expect("push error");
+            let a = array_obj.push_front(&1).expect("push error");
+            assert_eq!(array_obj.get_pre_index(a), None);
+            assert_eq!(array_obj.get_next_index(a), Some(b));
+            assert_eq!(array_obj.get_pre_index(c), Some(b));
+            assert_eq!(array_obj.get_next_index(c), None);
+            array_obj.check_integrity().expect("integrity error");
+
+            for _ in 0..(3 - round) {
+                array_obj.pop_last().expect("pop error");
+            }
+            while !array_obj.is_empty() {
+                array_obj.remove_at(array_obj.get_head_index().unwrap()).expect("remove error");
+            }
+            array_obj.check_integrity().expect("integrity error");
+
+            let only = array_obj.push_back(&9).expect("push error");
+            assert_eq!(array_obj.get_pre_index(only), None);
+            assert_eq!(array_obj.get_next_index(only), None);
+            array_obj.pop_last().expect("pop error");
+        }
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        assert_eq!(array_obj.peek_front(), None);
+        assert_eq!(array_obj.peek_back(), None);
+        let a = array_obj.push_back(&1).expect("push error");
+        assert_eq!(array_obj.peek_front(), Some((a, &1)));
+        assert_eq!(array_obj.peek_back(), Some((a, &1)));
+        let b = array_obj.push_front(&2).expect("push error");
+        let c = array_obj.push_back(&3).expect("push error");
+        assert_eq!(array_obj.peek_front(), Some((b, &2)));
+        assert_eq!(array_obj.peek_back(), Some((c, &3)));
+    }
+
+    #[test]
+    fn test_hash() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash_of(array: &QuickArray<i32>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            array.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let array_a = QuickArray::<i32>::from_slice(&[1, 2, 3]);
+        let mut array_b = QuickArray::<i32>::new(6);
+        array_b.push_front(&3).expect("push error");
+        array_b.push_front(&0).expect("push error");
+        let two = array_b.push_front(&2).expect("push error");
+        array_b.push_front(&1).expect("push error");
+        let zero = array_b.get_next_index(two).unwrap();
+        array_b.remove_at(zero).expect("remove error");
+
+        assert_eq!(array_a, array_b);
+        assert_eq!(hash_of(&array_a), hash_of(&array_b));
+        assert_ne!(hash_of(&array_a), hash_of(&QuickArray::<i32>::from_slice(&[3, 2, 1])));
+    }
+
+    #[test]
+    fn test_remove_range() {
+        let mut array_obj = QuickArray::<i32>::from_slice(&[0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(array_obj.remove_range(2, 3).expect("remove error"), 3);
+        assert_eq!(array_obj.to_vec(), vec![0, 1, 5, 6]);
+        assert_eq!(array_obj.remove_range(0, 1).expect("remove error"), 1);
+        assert_eq!(array_obj.get_head_element(), Some(&1));
+        assert_eq!(array_obj.remove_range(2, 10).expect("remove error"), 1);
+        assert_eq!(array_obj.get_tail_element(), Some(&5));
+        assert_eq!(array_obj.remove_range(2, 1).expect("remove error"), 0);
+        assert!(matches!(array_obj.remove_range(3, 1), Err(ErrDefine::InvalidIndex)));
+        array_obj.check_integrity().expect("integrity error");
+
+        assert_eq!(array_obj.remove_range(0, 2).expect("remove error"), 2);
+        assert!(array_obj.is_empty());
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_without_default() {
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        struct Order {
+            id: u32,
+            price: std::num::NonZeroU32,
+        }
+
+        let price = std::num::NonZeroU32::new(7).unwrap();
+        let mut array_obj = QuickArray::<Order>::new(2);
+        let a = array_obj.push_back(&Order { id: 1, price }).expect("push error");
+        array_obj.push_front(&Order { id: 2, price }).expect("push error");
+        assert_eq!(array_obj.to_vec().iter().map(|o| o.id).collect::<Vec<u32>>(), vec![2, 1]);
+        array_obj.remove_at(a).expect("remove error");
+        assert_eq!(array_obj.get_element(a), None);
+        assert!(format!("{:#?}", array_obj).contains("id: 2"));
+
+        let mut zst_array = QuickArray::<()>::new(3);
+        zst_array.push_back(&()).expect("push error");
+        zst_array.push_back(&()).expect("push error");
+        assert_eq!(zst_array.to_vec(), vec![(), ()]);
+    }
+
+    #[test]
+    fn test_clone_from() {
+        let source = QuickArray::<i32>::from_slice(&[1, 2, 3]);
+
+        let mut pooled = QuickArray::<i32>::from_slice(&[9, 8, 7, 6, 5]);
+        let buffer = pooled.internal_vec.as_ptr();
+        pooled.clone_from(&source);
+        assert_eq!(pooled.internal_vec.as_ptr(), buffer);
+        assert_eq!(pooled, source);
+        assert_eq!(pooled.capacity(), 3);
+        pooled.check_integrity().expect("integrity error");
+
+        let mut small = QuickArray::<i32>::new(1);
+        small.clone_from(&source);
+        assert_eq!(small.to_vec(), vec![1, 2, 3]);
+        small.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_insert_at_position() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        array_obj.insert_at_position(0, &2).expect("insert error");
+        array_obj.insert_at_position(0, &0).expect("insert error");
+        array_obj.insert_at_position(1, &1).expect("insert error");
+        let last = array_obj.insert_at_position(3, &4).expect("insert error");
+        array_obj.insert_at_position(3, &3).expect("insert error");
+        assert_eq!(array_obj.to_vec(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(array_obj.get_tail_index(), Some(last));
+        assert!(matches!(array_obj.insert_at_position(6, &6), Err(ErrDefine::InvalidIndex)));
+        assert!(matches!(array_obj.insert_at_position(5, &5), Err(ErrDefine::ArrayIsFull)));
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_is_valid_index() {
+        let mut array_obj = QuickArray::<i32>::new(2);
+        let a = array_obj.push_back(&1).expect("push error");
+        assert!(array_obj.is_valid_index(a));
+        assert!(!array_obj.is_valid_index(1));
+        assert!(!array_obj.is_valid_index(2));
+        array_obj.remove_at(a).expect("remove error");
+        assert!(!array_obj.is_valid_index(a));
+    }
+
+    #[test]
+    fn test_remove_many() {
+        let mut array_obj = QuickArray::<i32>::new(6);
+        let slots: Vec<u32> = (0..5).map(|i| array_obj.push_back(&i).expect("push error")).collect();
+        let removed = array_obj.remove_many(&[slots[0], slots[4], slots[0], 5, 100, slots[2]]);
+        assert_eq!(removed, 3);
+        assert_eq!(array_obj.to_vec(), vec![1, 3]);
+        assert_eq!(array_obj.len(), 2);
+        assert_eq!(array_obj.get_head_index(), Some(slots[1]));
+        assert_eq!(array_obj.get_tail_index(), Some(slots[3]));
+        array_obj.check_integrity().expect("integrity error");
+        assert_eq!(array_obj.remove_many(&[]), 0);
+    }
+
+    #[test]
+    fn test_with_capacity_and_values() {
+        let array_obj = QuickArray::<i32>::with_capacity_and_values(5, vec![1, 2, 3]).expect("build error");
+        assert_eq!(array_obj.capacity(), 5);
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3]);
+
+        let full = QuickArray::<i32>::with_capacity_and_values(3, 0..3).expect("build error");
+        assert!(full.is_full());
+        assert!(matches!(
+            QuickArray::<i32>::with_capacity_and_values(3, 0..4),
+            Err(ErrDefine::ArrayIsFull)
+        ));
+    }
+
+    #[test]
+    fn test_memory_footprint() {
+        let wide = QuickArray::<u8, u32>::new(100);
+        let narrow = QuickArray::<u8, u16>::new(100);
+        assert!(wide.memory_footprint() >= 100 * std::mem::size_of::<QuickElement<u8, u32>>());
+        assert!(narrow.memory_footprint() < wide.memory_footprint());
+    }
+
+    #[test]
+    fn test_update_with() {
+        let mut array_obj = QuickArray::<(u32, u32)>::new(2);
+        let a = array_obj.push_back(&(1, 0)).expect("push error");
+        for _ in 0..3 {
+            array_obj.update_with(a, |v| v.1 += 1).expect("update error");
+        }
+        assert_eq!(array_obj.get_element(a), Some(&(1, 3)));
+        assert!(matches!(array_obj.update_with(1, |v| v.1 += 1), Err(ErrDefine::InvalidIndex)));
+        assert!(matches!(array_obj.update_with(2, |v| v.1 += 1), Err(ErrDefine::InvalidIndex)));
+    }
+
+    #[test]
+    fn test_replace_front_back() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        assert!(matches!(array_obj.replace_front(&1), Err(ErrDefine::ArrayIsEmpty)));
+        assert!(matches!(array_obj.replace_back(&1), Err(ErrDefine::ArrayIsEmpty)));
+        array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        assert_eq!(array_obj.replace_front(&10).expect("replace error"), 1);
+        assert_eq!(array_obj.replace_back(&20).expect("replace error"), 2);
+        assert_eq!(array_obj.to_vec(), vec![10, 20]);
+    }
+
+    #[test]
+    fn test_links_at_index_type_limit() {
+        let max_size = u16::MAX as u32 - 1;
+        let mut array_obj = QuickArray::<u8, u16>::new(max_size);
+        for i in 0..max_size {
+            array_obj.push_back(&(i as u8)).expect("push error");
+        }
+        assert!(array_obj.is_full());
+        assert_eq!(array_obj.get_tail_index(), Some(max_size - 1));
+        assert_eq!(array_obj.get_next_index(max_size - 1), None);
+        assert_eq!(array_obj.get_pre_index(0), None);
+        assert_eq!(array_obj.free_indices().next(), None);
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.remove_at(max_size - 1).expect("remove error");
+        assert_eq!(array_obj.free_indices().collect::<Vec<u32>>(), vec![max_size - 1]);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_dedup_by_key() {
+        let mut array_obj = QuickArray::<i32>::new(9);
+        array_obj.dedup_by_key(|data| *data);
+        assert!(array_obj.is_empty());
+
+        array_obj.push_back(&1).expect("push error");
+        array_obj.dedup_by_key(|data| *data);
+        assert_eq!(array_obj.to_vec(), vec![1]);
+
+        for data in &[1, 2, 2, 3, -3, 3, 4, 4] {
+            array_obj.push_back(data).expect("push error");
+        }
+        let kept = array_obj.get_by_position(4).expect("position error").0;
+        array_obj.dedup_by_key(|data| data.abs());
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3, 4]);
+        assert_eq!(array_obj.get_element(kept), Some(&3));
+        assert_eq!(array_obj.get_tail_element(), Some(&4));
+        assert_eq!(array_obj.len(), 4);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_get_many_mut() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        let c = array_obj.push_back(&3).expect("push error");
+
+        let [x, y] = array_obj.get_many_mut([a, c]).expect("get error");
+        mem::swap(x, y);
+        *y += 10;
+        assert_eq!(array_obj.to_vec(), vec![3, 2, 11]);
+
+        assert!(array_obj.get_many_mut([a, a]).is_none());
+        assert!(array_obj.get_many_mut([b, 3]).is_none());
+        assert!(array_obj.get_many_mut([b, 4]).is_none());
+        assert_eq!(array_obj.get_many_mut::<0>([]).map(|refs| refs.len()), Some(0));
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_first_last() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        assert_eq!(array_obj.first(), None);
+        assert_eq!(array_obj.last_mut(), None);
+
+        array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        *array_obj.first_mut().expect("first error") += 10;
+        *array_obj.last_mut().expect("last error") += 20;
+        assert_eq!(array_obj.first(), Some(&11));
+        assert_eq!(array_obj.last(), Some(&22));
+        assert_eq!(array_obj.first(), array_obj.get_head_element());
+        assert_eq!(array_obj.last(), array_obj.get_tail_element());
+    }
+
+    #[test]
+    fn test_find_map() {
+        let mut array_obj = QuickArray::<(u32, char)>::new(4);
+        assert_eq!(array_obj.find_map(|data| Some(data.1)), None);
+
+        array_obj.push_back(&(1, 'a')).expect("push error");
+        array_obj.push_back(&(2, 'b')).expect("push error");
+        array_obj.push_back(&(4, 'c')).expect("push error");
+
+        let mut visited = 0;
+        let found = array_obj.find_map(|data| {
+            visited += 1;
+            if data.0 % 2 == 0 { Some(data.1) } else { None }
+        });
+        assert_eq!(found, Some('b'));
+        assert_eq!(visited, 2);
+        assert_eq!(array_obj.find_map(|data| if data.0 > 4 { Some(data.1) } else { None }), None);
+    }
+
+    #[test]
+    fn test_export_import_raw() {
+        let mut array_obj = QuickArray::<i32, u16>::new(5);
+        array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        array_obj.push_front(&0).expect("push error");
+        array_obj.remove_at(b).expect("remove error");
+
+        let snapshot = array_obj.export_raw();
+        assert_eq!(snapshot.elements.len(), 5);
+        assert_eq!(snapshot.elements[b as usize].0, None);
+
+        let restored = QuickArray::<i32, u16>::import_raw(snapshot.clone()).expect("import error");
+        assert_eq!(restored, array_obj);
+        assert_eq!(restored.export_raw(), snapshot);
+        assert_eq!(restored.free_indices().collect::<Vec<u32>>(), array_obj.free_indices().collect::<Vec<u32>>());
+        restored.check_integrity().expect("integrity error");
+
+        let mut broken = snapshot.clone();
+        broken.elements.pop();
+        assert!(matches!(QuickArray::<i32, u16>::import_raw(broken), Err(ErrDefine::ArraySizeError)));
+
+        let mut broken = snapshot.clone();
+        broken.elements[0].2 = Some(70000);
+        assert!(matches!(QuickArray::<i32, u16>::import_raw(broken), Err(ErrDefine::Corrupted)));
+
+        let mut broken = snapshot.clone();
+        broken.valid_tail = broken.valid_head;
+        assert!(matches!(QuickArray::<i32, u16>::import_raw(broken), Err(ErrDefine::Corrupted)));
+
+        let mut broken = snapshot;
+        broken.elements[b as usize].0 = Some(7);
+        assert!(matches!(QuickArray::<i32, u16>::import_raw(broken), Err(ErrDefine::Corrupted)));
+    }
+
+    #[test]
+    fn test_indices() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert_eq!(array_obj.indices().next(), None);
+
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_front(&2).expect("push error");
+        let c = array_obj.insert_after(b, &3).expect("insert error");
+        assert_eq!(array_obj.indices().collect::<Vec<u32>>(), vec![b, c, a]);
+        assert!(array_obj.indices().eq(array_obj.iter().map(|item| item.0)));
+
+        array_obj.remove_at(c).expect("remove error");
+        assert_eq!(array_obj.indices().collect::<Vec<u32>>(), vec![b, a]);
+    }
+
+    fn corrupted_array() -> QuickArray<i32> {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        array_obj.internal_vec[1].cur = 2;
+        array_obj
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn test_corrupted_slot() {
+        let mut array_obj = corrupted_array();
+        assert!(matches!(array_obj.remove_at(1), Err(ErrDefine::Corrupted)));
+        assert!(matches!(array_obj.update_at(1, &3), Err(ErrDefine::Corrupted)));
+        assert!(matches!(array_obj.insert_after(1, &3), Err(ErrDefine::Corrupted)));
+        assert!(matches!(array_obj.insert_before(1, &3), Err(ErrDefine::Corrupted)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_corrupted_slot_debug() {
+        // debug builds stop at the assertion before returning `Corrupted`
+        let results = [
+            std::panic::catch_unwind(|| corrupted_array().remove_at(1).map(|_| ())),
+            std::panic::catch_unwind(|| corrupted_array().update_at(1, &3)),
+            std::panic::catch_unwind(|| corrupted_array().insert_after(1, &3).map(|_| ())),
+            std::panic::catch_unwind(|| corrupted_array().insert_before(1, &3).map(|_| ())),
+        ];
+        for result in results {
+            let message = result.expect_err("corruption must be caught");
+            let message = message.downcast_ref::<String>().expect("panic message");
+            assert!(message.contains("index calculation goes wrong"));
+        }
+    }
+
+    #[test]
+    fn test_clear_and_resize() {
+        let mut array_obj = QuickArray::<i32>::new(8);
+        for i in 0..5 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        let handle = array_obj.get_handle(0).expect("handle error");
+        let buffer = array_obj.internal_vec.as_ptr();
+
+        array_obj.clear_and_resize(3);
+        assert!(array_obj.is_empty());
+        assert_eq!(array_obj.capacity(), 3);
+        assert_eq!(array_obj.internal_vec.as_ptr(), buffer);
+        assert_eq!(array_obj.get_by_handle(handle), None);
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.push_back(&1).expect("push error");
+        array_obj.clear_and_resize(6);
+        assert_eq!(array_obj.internal_vec.as_ptr(), buffer);
+        assert_eq!(array_obj.capacity(), 6);
+        for i in 0..6 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.clear_and_resize(12);
+        assert_eq!(array_obj.capacity(), 12);
+        assert!(array_obj.is_empty());
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.clear_and_resize(0);
+        assert_eq!(array_obj.capacity(), 1);
+
+        // slots dropped by the shrink must not revive old handles once they come back
+        let mut array_obj = QuickArray::<i32>::new(4);
+        for i in 0..4 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        let stale = array_obj.get_handle(3).expect("handle error");
+        array_obj.clear_and_resize(2);
+        array_obj.clear_and_resize(4);
+        for i in 50..54 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        assert!(array_obj.is_valid_index(stale.index));
+        assert_eq!(array_obj.get_by_handle(stale), None);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_iter_from() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        assert!(array_obj.iter_from(0).is_none());
+
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        let c = array_obj.push_back(&3).expect("push error");
+        array_obj.push_front(&0).expect("push error");
+
+        let iter = array_obj.iter_from(b).expect("iter error");
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.collect::<Vec<(u32, &i32)>>(), vec![(b, &2), (c, &3)]);
+        assert_eq!(array_obj.iter_from(a).expect("iter error").count(), 3);
+
+        array_obj.remove_at(b).expect("remove error");
+        assert!(array_obj.iter_from(b).is_none());
+        assert!(array_obj.iter_from(5).is_none());
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut array_obj = QuickArray::<(u32, char)>::new(2);
+        let a = array_obj.entry(|data| data.0 == 1).or_insert(&(1, 'a')).expect("insert error");
+        assert_eq!(array_obj.entry(|data| data.0 == 1).or_insert(&(1, 'z')).expect("insert error"), a);
+        assert_eq!(array_obj.get_element(a), Some(&(1, 'a')));
+
+        match array_obj.entry(|data| data.0 == 2) {
+            Entry::Occupied(_) => panic!("entry must be vacant"),
+            Entry::Vacant(entry) => {
+                entry.insert(&(2, 'b')).expect("insert error");
+            }
+        }
+        assert_eq!(array_obj.to_vec(), vec![(1, 'a'), (2, 'b')]);
+
+        assert!(matches!(array_obj.entry(|data| data.0 == 3).or_insert(&(3, 'c')), Err(ErrDefine::ArrayIsFull)));
+        assert!(matches!(array_obj.entry(|data| data.1 == 'b'), Entry::Occupied(index) if index != a));
+    }
+
+    #[test]
+    fn test_swap_remove() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert!(matches!(array_obj.swap_remove(0), Err(ErrDefine::InvalidIndex)));
+
+        let a = array_obj.push_back_handle(&1).expect("push error");
+        let b = array_obj.push_back_handle(&2).expect("push error");
+        let c = array_obj.push_back_handle(&3).expect("push error");
+
+        assert_eq!(array_obj.swap_remove(a.index).expect("remove error"), 1);
+        assert_eq!(array_obj.to_vec(), vec![3, 2]);
+        assert_eq!(array_obj.get_by_handle(a), Some(&3));
+        assert_eq!(array_obj.get_by_handle(c), None);
+        array_obj.check_integrity().expect("integrity error");
+
+        assert_eq!(array_obj.swap_remove(b.index).expect("remove error"), 2);
+        assert_eq!(array_obj.to_vec(), vec![3]);
+        assert!(matches!(array_obj.swap_remove(b.index), Err(ErrDefine::InvalidIndex)));
+        assert!(matches!(array_obj.swap_remove(9), Err(ErrDefine::InvalidIndex)));
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_count_where() {
+        let mut array_obj = QuickArray::<i32>::new(6);
+        assert_eq!(array_obj.count_where(|_| true), 0);
+
+        for i in 0..6 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        array_obj.remove_at(2).expect("remove error");
+        assert_eq!(array_obj.count_where(|data| data % 2 == 0), 2);
+        assert_eq!(array_obj.count_where(|_| true), array_obj.len() as u32);
+        assert_eq!(array_obj.count_where(|data| *data > 10), 0);
+    }
+
+    #[test]
+    fn test_default() {
+        #[derive(Default)]
+        struct Config {
+            recent: QuickArray<u32>,
+        }
+
+        let mut config = Config::default();
+        assert!(config.recent.is_empty());
+        assert_eq!(config.recent.capacity(), 1);
+        config.recent.push_back(&1).expect("push error");
+        assert!(config.recent.is_full());
+        config.recent.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let mut array_obj = QuickArray::<(i32, char)>::new(7);
+        for data in &[(1, 'a'), (3, 'a'), (5, 'a')] {
+            array_obj.push_back(data).expect("push error");
+        }
+        let mut other = QuickArray::<(i32, char)>::new(4);
+        for data in &[(0, 'b'), (3, 'b'), (4, 'b'), (9, 'b')] {
+            other.push_back(data).expect("push error");
+        }
+
+        let mut small = QuickArray::<(i32, char)>::new(4);
+        small.push_back(&(2, 'c')).expect("push error");
+        assert!(matches!(small.merge_sorted(&mut other, |a, b| a.0.cmp(&b.0)), Err(ErrDefine::ArrayIsFull)));
+        assert_eq!(other.len(), 4);
+        assert_eq!(small.to_vec(), vec![(2, 'c')]);
+
+        array_obj.merge_sorted(&mut other, |a, b| a.0.cmp(&b.0)).expect("merge error");
+        assert_eq!(
+            array_obj.to_vec(),
+            vec![(0, 'b'), (1, 'a'), (3, 'a'), (3, 'b'), (4, 'b'), (5, 'a'), (9, 'b')]
+        );
+        assert!(other.is_empty());
+        array_obj.check_integrity().expect("integrity error");
+        other.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_index_snapshot() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert!(array_obj.index_snapshot().is_empty());
+
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_front(&2).expect("push error");
+        let snapshot = array_obj.index_snapshot();
+        assert_eq!(snapshot, vec![b, a]);
+
+        array_obj.remove_at(b).expect("remove error");
+        array_obj.push_back(&3).expect("push error");
+        let values: Vec<Option<&i32>> = snapshot.iter().map(|index| array_obj.get_element(*index)).collect();
+        assert_eq!(values, vec![Some(&3), Some(&1)]);
+        assert_eq!(array_obj.index_snapshot(), vec![a, b]);
+    }
+
+    #[test]
+    fn test_head_tail_index() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert_eq!(array_obj.get_head_index(), None);
+        assert_eq!(array_obj.get_tail_index(), None);
+
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_front(&2).expect("push error");
+        assert_eq!(array_obj.get_head_index(), Some(b));
+        assert_eq!(array_obj.get_tail_index(), Some(a));
+
+        let c = array_obj.push_front(&3).expect("push error");
+        array_obj.internal_vec[c as usize].cur = b;
+        assert_eq!(array_obj.get_head_index(), Some(c));
+    }
+
+    #[test]
+    fn test_for_each_mut() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        array_obj.for_each_mut(|_, _| panic!("array is empty"));
+
+        let a = array_obj.push_back(&10).expect("push error");
+        let b = array_obj.push_front(&20).expect("push error");
+        let c = array_obj.push_back(&30).expect("push error");
+
+        let mut visited = Vec::new();
+        array_obj.for_each_mut(|index, data| {
+            visited.push(index);
+            *data /= 10;
+        });
+        assert_eq!(visited, vec![b, a, c]);
+        assert_eq!(array_obj.to_vec(), vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn test_backing_capacity() {
+        let mut array_obj = QuickArray::<i32>::new(16);
+        assert!(array_obj.backing_capacity() >= 16);
+
+        array_obj.push_back(&1).expect("push error");
+        array_obj.shrink_to(2).expect("shrink error");
+        assert_eq!(array_obj.capacity(), 2);
+        assert!(array_obj.backing_capacity() >= 16);
+
+        array_obj.shrink_backing_to_fit();
+        assert!(array_obj.backing_capacity() >= 2 && array_obj.backing_capacity() < 16);
+        assert_eq!(array_obj.to_vec(), vec![1]);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_key_lookup() {
+        let mut array_obj = QuickArray::<(u32, char)>::new(4);
+        let a = array_obj.push_back(&(1, 'a')).expect("push error");
+        let b = array_obj.push_back(&(2, 'b')).expect("push error");
+        array_obj.push_back(&(1, 'c')).expect("push error");
+
+        let lookup = array_obj.rebuild_lookup(|data| data.0);
+        assert_eq!(lookup.len(), 2);
+        assert_eq!(lookup.get_index(&1), Some(a));
+        assert_eq!(lookup.get_by_key(&array_obj, &2), Some(&(2, 'b')));
+        assert_eq!(lookup.get_by_key(&array_obj, &3), None);
+
+        array_obj.remove_at(b).expect("remove error");
+        array_obj.push_back(&(2, 'd')).expect("push error");
+        assert_eq!(lookup.get_by_key(&array_obj, &2), None);
+
+        let lookup = array_obj.rebuild_lookup(|data| data.0);
+        assert_eq!(lookup.get_by_key(&array_obj, &2), Some(&(2, 'd')));
+    }
+
+    #[test]
+    fn test_drain_while() {
+        let mut array_obj = QuickArray::<u32>::new(5);
+        assert!(array_obj.drain_while(|_| true).is_empty());
+
+        for data in &[1, 2, 5, 3, 8] {
+            array_obj.push_back(data).expect("push error");
+        }
+        assert!(array_obj.drain_while(|data| *data > 1).is_empty());
+        assert_eq!(array_obj.len(), 5);
+
+        assert_eq!(array_obj.drain_while(|data| *data < 4), vec![1, 2]);
+        assert_eq!(array_obj.to_vec(), vec![5, 3, 8]);
+        array_obj.check_integrity().expect("integrity error");
+
+        assert_eq!(array_obj.drain_while(|_| true), vec![5, 3, 8]);
+        assert!(array_obj.is_empty());
+        assert_eq!(array_obj.get_tail_index(), None);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_vec_conversions() {
+        let array_obj = QuickArray::<i32>::from(vec![3, 1, 2]);
+        assert_eq!(array_obj.capacity(), 3);
+        assert!(array_obj.is_full());
+        let values: Vec<i32> = array_obj.into();
+        assert_eq!(values, vec![3, 1, 2]);
+
+        let empty: QuickArray<i32> = Vec::new().into();
+        assert!(empty.is_empty());
+        assert_eq!(Vec::from(empty), Vec::<i32>::new());
+
+        let slice: &[i32] = &[4, 5];
+        let array_obj: QuickArray<i32, u16> = slice.into();
+        assert_eq!(array_obj.to_vec(), vec![4, 5]);
+        assert!(array_obj.is_full());
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    #[should_panic(expected = "too large")]
+    fn test_from_vec_too_large() {
+        let _array_obj = QuickArray::<u8, u16>::from(vec![0u8; u16::MAX as usize]);
+    }
+
+    #[test]
+    fn test_try_from_slice() {
+        let data = vec![7u8; 300];
+        assert!(matches!(QuickArray::<u8, u8>::try_from_slice(&data), Err(ErrDefine::ArraySizeError)));
+
+        let array_obj = QuickArray::<u8, u16>::try_from_slice(&data).expect("size error");
+        assert_eq!(array_obj.len(), 300);
+        assert_eq!(array_obj.to_vec(), data);
+
+        let array_obj = QuickArray::<u8, u8>::try_from_slice(&[]).expect("size error");
+        assert!(array_obj.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_one() {
+        let mut array_obj = QuickArray::<i32>::new(1);
+        array_obj.check_integrity().expect("integrity error");
+        assert!(matches!(array_obj.pop_last(), Err(ErrDefine::ArrayIsEmpty)));
+        assert!(matches!(array_obj.insert_after(0, &1), Err(ErrDefine::InvalidIndex)));
+        assert!(matches!(array_obj.insert_before(0, &1), Err(ErrDefine::InvalidIndex)));
+
+        for round in 0..3 {
+            let index = if round % 2 == 0 {
+                array_obj.push_back(&round).expect("push error")
+            } else {
+                array_obj.push_front(&round).expect("push error")
+            };
+            assert_eq!(index, 0);
+            assert_eq!(array_obj.get_head_index(), Some(0));
+            assert_eq!(array_obj.get_tail_index(), Some(0));
+            assert_eq!(array_obj.get_pre_index(0), None);
+            assert_eq!(array_obj.get_next_index(0), None);
+            assert!(array_obj.is_full());
+            array_obj.check_integrity().expect("integrity error");
+
+            assert!(matches!(array_obj.push_back(&9), Err(ErrDefine::ArrayIsFull)));
+            assert!(matches!(array_obj.push_front(&9), Err(ErrDefine::ArrayIsFull)));
+            assert!(matches!(array_obj.insert_after(0, &9), Err(ErrDefine::ArrayIsFull)));
+            assert!(matches!(array_obj.insert_before(0, &9), Err(ErrDefine::ArrayIsFull)));
+            assert_eq!(array_obj.to_vec(), vec![round]);
+            array_obj.check_integrity().expect("integrity error");
+
+            if round == 1 {
+                array_obj.pop_last().expect("pop error");
+            } else {
+                array_obj.remove_at(0).expect("remove error");
+            }
+            assert!(array_obj.is_empty());
+            assert_eq!(array_obj.get_head_index(), None);
+            assert_eq!(array_obj.get_tail_index(), None);
+            assert_eq!(array_obj.free_indices().collect::<Vec<u32>>(), vec![0]);
+            assert!(matches!(array_obj.remove_at(0), Err(ErrDefine::InvalidIndex)));
+            array_obj.check_integrity().expect("integrity error");
         }
-        let array_val:Vec<i32>= array_obj.enumerate().map(|item|*item.1) .collect();
-        assert_eq!(array_val.len(),LRU_LEN as usize);
-        assert_eq!(array_obj.get_valid_count (),LRU_LEN);
-        for index in 0..array_val.len(){
-            assert_eq!(array_val[index],total_data[total_data.len()-index-1])
+    }
+
+    #[test]
+    fn test_evicting_array() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let evicted = Rc::new(RefCell::new(Vec::<(u32, i32)>::new()));
+        let mut array_obj = EvictingQuickArray::<i32>::new(3);
+        let sink = Rc::clone(&evicted);
+        array_obj.on_evict(move |index, data| sink.borrow_mut().push((index, *data)));
+
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        let c = array_obj.push_back(&3).expect("push error");
+        assert_eq!(array_obj.len(), 3);
+
+        let (d, value) = array_obj.push_front_evicting(&0);
+        assert_eq!(value, Some(3));
+        array_obj.pop_last().expect("pop error");
+        array_obj.remove_at(d).expect("remove error");
+        assert!(matches!(array_obj.remove_at(d), Err(ErrDefine::InvalidIndex)));
+        assert_eq!(*(*evicted).borrow(), vec![(c, 3), (b, 2), (d, 0)]);
+
+        (*evicted).borrow_mut().clear();
+        array_obj.push_back(&4).expect("push error");
+        array_obj.push_back(&5).expect("push error");
+        assert_eq!(array_obj.remove_range(1, 5).expect("remove error"), 2);
+        let values: Vec<i32> = (*evicted).borrow().iter().map(|item| item.1).collect();
+        assert_eq!(values, vec![4, 5]);
+        assert_eq!(array_obj.to_vec(), vec![1]);
+
+        let inner = array_obj.into_inner();
+        assert_eq!(inner.get_element(a), Some(&1));
+        inner.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_retain_visit() {
+        let mut array_obj = QuickArray::<i32>::new(6);
+        array_obj.retain_visit(|_, _| false);
+
+        for i in 0..6 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        let mut visited = Vec::new();
+        array_obj.retain_visit(|index, data| {
+            visited.push(index);
+            data % 3 != 0
+        });
+        assert_eq!(visited, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 4, 5]);
+        assert_eq!(array_obj.get_head_index(), Some(1));
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.retain_visit(|_, _| false);
+        assert!(array_obj.is_empty());
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_push_back_growing() {
+        let mut array_obj = QuickArray::<i32>::new(2);
+        array_obj.push_back_growing(&0, 0).expect("push error");
+        array_obj.push_back_growing(&1, 0).expect("push error");
+        assert_eq!(array_obj.capacity(), 2);
+
+        array_obj.push_back_growing(&2, 0).expect("push error");
+        assert_eq!(array_obj.capacity(), 4);
+        array_obj.push_back_growing(&3, 3).expect("push error");
+        array_obj.push_back_growing(&4, 3).expect("push error");
+        assert_eq!(array_obj.capacity(), 7);
+        assert_eq!(array_obj.to_vec(), vec![0, 1, 2, 3, 4]);
+        array_obj.check_integrity().expect("integrity error");
+
+        // growing by one slot per push still leaves spare buffer capacity most of the time
+        let mut resizes = 0;
+        for i in 5..1000 {
+            let backing = array_obj.backing_capacity();
+            array_obj.push_back_growing(&i, 1).expect("push error");
+            if array_obj.backing_capacity() != backing {
+                resizes += 1;
+            }
+        }
+        assert_eq!(array_obj.capacity(), 1000);
+        assert!(resizes <= 12, "{} resizes", resizes);
+
+        let mut small = QuickArray::<u8, u16>::new(u16::MAX as u32 - 1);
+        for i in 0..small.capacity() {
+            small.push_back(&(i as u8)).expect("push error");
+        }
+        assert!(matches!(small.push_back_growing(&0, 1), Err(ErrDefine::ArraySizeError)));
+        small.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_remove_value() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert_eq!(array_obj.remove_value(&1), None);
+
+        array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        let c = array_obj.push_back(&2).expect("push error");
+        assert_eq!(array_obj.remove_value(&2), Some(b));
+        assert_eq!(array_obj.to_vec(), vec![1, 2]);
+        assert_eq!(array_obj.remove_value(&2), Some(c));
+        assert_eq!(array_obj.remove_value(&2), None);
+        assert_eq!(array_obj.to_vec(), vec![1]);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut array_obj = QuickArray::<i32>::new(6);
+        for i in 0..6 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        array_obj.remove_many(&[0, 2, 3]);
+        let d = array_obj.push_front(&9).expect("push error");
+        let handle = array_obj.get_handle(5).expect("handle error");
+
+        let remap = array_obj.compact();
+        assert_eq!(remap, vec![(d, 0), (1, 1), (4, 2), (5, 3)]);
+        assert_eq!(array_obj.to_vec(), vec![9, 1, 4, 5]);
+        assert_eq!(array_obj.indices().collect::<Vec<u32>>(), vec![0, 1, 2, 3]);
+        assert_eq!(array_obj.free_indices().collect::<Vec<u32>>(), vec![4, 5]);
+        assert_eq!(array_obj.get_by_handle(handle), None);
+        array_obj.check_integrity().expect("integrity error");
+
+        let mut empty = QuickArray::<i32>::new(3);
+        assert!(empty.compact().is_empty());
+        empty.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_remove_at_returns_value() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        assert_eq!(array_obj.remove_at(b).expect("remove error"), 2);
+        assert_eq!(array_obj.remove_at(a).expect("remove error"), 1);
+        assert!(matches!(array_obj.remove_at(a), Err(ErrDefine::InvalidIndex)));
+        assert!(array_obj.is_empty());
+    }
+
+    #[test]
+    fn test_pop_front_back() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        assert_eq!(array_obj.pop_front(), None);
+        assert_eq!(array_obj.pop_back(), None);
+
+        array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        array_obj.push_front(&0).expect("push error");
+        assert_eq!(array_obj.pop_front(), Some(0));
+        assert_eq!(array_obj.pop_back(), Some(2));
+        assert_eq!(array_obj.get_head_index(), array_obj.get_tail_index());
+        array_obj.check_integrity().expect("integrity error");
+        assert_eq!(array_obj.pop_back(), Some(1));
+        assert_eq!(array_obj.pop_front(), None);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_get_element_mut() {
+        let mut array_obj = QuickArray::<(u32, u32)>::new(3);
+        let a = array_obj.push_back(&(1, 0)).expect("push error");
+        let b = array_obj.push_back(&(2, 0)).expect("push error");
+
+        array_obj.get_element_mut(a).expect("get error").1 += 1;
+        assert_eq!(array_obj.get_element(a), Some(&(1, 1)));
+
+        array_obj.remove_at(b).expect("remove error");
+        assert_eq!(array_obj.get_element_mut(b), None);
+        assert_eq!(array_obj.get_element_mut(3), None);
+    }
+
+    #[test]
+    fn test_head_tail_element_mut() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        assert_eq!(array_obj.get_head_element_mut(), None);
+        assert_eq!(array_obj.get_tail_element_mut(), None);
+
+        array_obj.push_back(&1).expect("push error");
+        *array_obj.get_head_element_mut().expect("head error") += 10;
+        assert_eq!(array_obj.get_tail_element(), Some(&11));
+
+        array_obj.push_back(&2).expect("push error");
+        *array_obj.get_tail_element_mut().expect("tail error") *= 3;
+        assert_eq!(array_obj.to_vec(), vec![11, 6]);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert_eq!(array_obj.iter_mut().next(), None);
+
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_front(&2).expect("push error");
+        let c = array_obj.push_back(&3).expect("push error");
+
+        let iter = array_obj.iter_mut();
+        assert_eq!(iter.len(), 3);
+        let mut visited = Vec::new();
+        for (index, data) in iter {
+            visited.push(index);
+            *data *= 10;
+        }
+        assert_eq!(visited, vec![b, a, c]);
+        assert_eq!(array_obj.to_vec(), vec![20, 10, 30]);
+
+        let iter = array_obj.iter_mut();
+        std::thread::scope(|scope| {
+            scope.spawn(move || iter.for_each(|(_, data)| *data += 1));
+        });
+        assert_eq!(array_obj.to_vec(), vec![21, 11, 31]);
+
+        let refs: Vec<&mut i32> = array_obj.iter_mut().map(|item| item.1).collect();
+        assert_eq!(refs.len(), 3);
+    }
+
+    #[test]
+    fn test_double_ended_iterator() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        assert_eq!(array_obj.rev_enumerate().next(), None);
+
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        let c = array_obj.push_front(&0).expect("push error");
+        let d = array_obj.push_back(&3).expect("push error");
+
+        assert_eq!(array_obj.rev_enumerate().collect::<Vec<(u32, &i32)>>(), vec![(d, &3), (b, &2), (a, &1), (c, &0)]);
+
+        let mut iter = array_obj.iter();
+        assert_eq!(iter.next(), Some((c, &0)));
+        assert_eq!(iter.next_back(), Some((d, &3)));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next_back(), Some((b, &2)));
+        assert_eq!(iter.next(), Some((a, &1)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let tail: Vec<i32> = array_obj.iter_from(a).expect("iter error").rev().map(|item| *item.1).collect();
+        assert_eq!(tail, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        array_obj.push_front(&0).expect("push error");
+
+        let mut total = 0;
+        for data in array_obj.clone() {
+            total += data;
+        }
+        assert_eq!(total, 3);
+
+        let iter = array_obj.clone().into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.collect::<Vec<i32>>(), vec![0, 1, 2]);
+        assert_eq!(array_obj.into_iter().rev().collect::<Vec<i32>>(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_into_iter_refs() {
+        fn sum_values<'a, Iter: IntoIterator<Item = (u32, &'a i32)>>(iter: Iter) -> i32 {
+            iter.into_iter().map(|item| *item.1).sum()
+        }
+
+        let mut array_obj = QuickArray::<i32>::new(3);
+        let a = array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+
+        for (index, data) in &mut array_obj {
+            if index == a {
+                *data = 5;
+            }
+        }
+        let mut visited = Vec::new();
+        for (_, data) in &array_obj {
+            visited.push(*data);
+        }
+        assert_eq!(visited, vec![5, 2]);
+        assert_eq!(sum_values(&array_obj), 7);
+    }
+
+    #[test]
+    fn test_fused_iterators() {
+        fn assert_fused<Iter: FusedIterator + ExactSizeIterator>(mut iter: Iter, len: usize) {
+            assert_eq!(iter.size_hint(), (len, Some(len)));
+            for _ in 0..len {
+                assert!(iter.next().is_some());
+            }
+            assert!(iter.next().is_none());
+            assert!(iter.next().is_none());
+            assert_eq!(iter.len(), 0);
+        }
+
+        let mut array_obj = QuickArray::<i32>::new(3);
+        array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        assert_fused(array_obj.iter(), 2);
+        assert_fused(array_obj.iter_mut(), 2);
+        assert_fused(array_obj.clone().into_iter(), 2);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        for i in 0..4 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        array_obj.remove_at(1).expect("remove error");
+
+        let drained: Vec<i32> = array_obj.drain().collect();
+        assert_eq!(drained, vec![0, 2, 3]);
+        assert!(array_obj.is_empty());
+        assert_eq!(array_obj.free_indices().collect::<Vec<u32>>(), vec![0, 1, 2, 3]);
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.push_back(&7).expect("push error");
+        array_obj.push_back(&8).expect("push error");
+        array_obj.push_back(&9).expect("push error");
+        let mut drain = array_obj.drain();
+        assert_eq!(drain.len(), 3);
+        assert_eq!(drain.next_back(), Some(9));
+        drop(drain);
+        assert!(array_obj.is_empty());
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut array_obj = QuickArray::<i32>::new(6);
+        assert_eq!(array_obj.extract_if(|_| true).next(), None);
+
+        for i in 0..6 {
+            array_obj.push_back(&i).expect("push error");
+        }
+        let odd: Vec<i32> = array_obj.extract_if(|data| data % 2 == 1).collect();
+        assert_eq!(odd, vec![1, 3, 5]);
+        assert_eq!(array_obj.to_vec(), vec![0, 2, 4]);
+        assert_eq!(array_obj.get_tail_index(), Some(4));
+        array_obj.check_integrity().expect("integrity error");
+
+        assert_eq!(array_obj.extract_if(|_| true).next(), Some(0));
+        assert_eq!(array_obj.to_vec(), vec![2, 4]);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        for i in 0..5 {
+            array_obj.push_front(&i).expect("push error");
+        }
+        array_obj.retain(|data| *data != 4 && *data != 2);
+        assert_eq!(array_obj.to_vec(), vec![3, 1, 0]);
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.retain(|data| *data == 0);
+        assert_eq!(array_obj.peek_front(), array_obj.peek_back());
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_retain_mut() {
+        let mut array_obj = QuickArray::<(char, u32)>::new(4);
+        array_obj.push_back(&('a', 1)).expect("push error");
+        array_obj.push_back(&('b', 3)).expect("push error");
+        array_obj.push_back(&('c', 2)).expect("push error");
+
+        array_obj.retain_mut(|data| {
+            data.1 -= 1;
+            data.1 > 0
+        });
+        assert_eq!(array_obj.to_vec(), vec![('b', 2), ('c', 1)]);
+        array_obj.retain_mut(|data| {
+            data.1 -= 1;
+            data.1 > 0
+        });
+        assert_eq!(array_obj.to_vec(), vec![('b', 1)]);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut array_obj = QuickArray::<i32>::new(5);
+        array_obj.extend(vec![1, 2]);
+        array_obj.extend(&[3, 4]);
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3, 4]);
+
+        assert_eq!(array_obj.try_extend(5..9), 3);
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3, 4, 5]);
+        array_obj.extend([6, 7].iter());
+        assert_eq!(array_obj.len(), 5);
+
+        array_obj.pop_front();
+        assert_eq!(array_obj.try_extend(Some(6)), 0);
+        assert_eq!(array_obj.to_vec(), vec![2, 3, 4, 5, 6]);
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_from_iterator() {
+        let array_obj: QuickArray<u32> = (0..4).collect();
+        assert_eq!(array_obj.capacity(), 4);
+        assert_eq!(array_obj.to_vec(), vec![0, 1, 2, 3]);
+        array_obj.check_integrity().expect("integrity error");
+
+        let filtered: QuickArray<u32, u16> = array_obj.iter().map(|item| *item.1).filter(|data| data % 2 == 0).collect();
+        assert_eq!(filtered.to_vec(), vec![0, 2]);
+        assert!(filtered.is_full());
+
+        let empty: QuickArray<u32> = core::iter::empty().collect();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_eq_in_hash_set() {
+        use std::collections::HashSet;
+
+        let mut a = QuickArray::<i32>::new(4);
+        a.push_back(&2).expect("push error");
+        a.push_front(&1).expect("push error");
+        let b = QuickArray::<i32>::from_slice(&[1, 2]);
+        let c = QuickArray::<i32>::from_slice(&[2, 1]);
+
+        let mut set = HashSet::new();
+        assert!(set.insert(a));
+        assert!(!set.insert(b));
+        assert!(set.insert(c));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_std_names() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+
+        assert_eq!(array_obj.len(), 2);
+        assert_eq!(array_obj.capacity(), 4);
+        assert_eq!(array_obj.len(), array_obj.get_valid_count() as usize);
+        assert_eq!(array_obj.capacity(), array_obj.get_max_size() as usize);
+        assert!(array_obj.iter().eq(array_obj.enumerate()));
+    }
+
+    #[test]
+    fn test_index_mut() {
+        let mut array_obj = QuickArray::<(u32, u32)>::new(3);
+        let a = array_obj.push_back(&(1, 0)).expect("push error");
+        array_obj[a].1 += 5;
+        array_obj[a] = (array_obj[a].0 + 1, array_obj[a].1);
+        assert_eq!(array_obj[a], (2, 5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_mut_recycled_slot() {
+        let mut array_obj = QuickArray::<i32>::new(3);
+        let a = array_obj.push_back(&1).expect("push error");
+        array_obj.remove_at(a).expect("remove error");
+        array_obj[a] = 2;
+    }
+
+    #[test]
+    fn test_fmt_chain() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert_eq!(array_obj.fmt_chain().to_string(), "head -> tail (4 free)");
+
+        array_obj.push_back(&5).expect("push error");
+        array_obj.push_front(&7).expect("push error");
+        assert_eq!(array_obj.fmt_chain().to_string(), "head -> [1]=7 -> [0]=5 -> tail (2 free)");
+    }
+
+    #[test]
+    fn test_display() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert_eq!(array_obj.to_string(), "[]");
+
+        array_obj.push_back(&2).expect("push error");
+        array_obj.push_front(&1).expect("push error");
+        array_obj.push_back(&3).expect("push error");
+        assert_eq!(array_obj.to_string(), "[1, 2, 3]");
+        assert_eq!(format!("{:>2}", array_obj), "[ 1,  2,  3]");
+    }
+
+    #[test]
+    fn test_to_from_bytes() {
+        let mut array_obj = QuickArray::<i64, u16>::new(5);
+        array_obj.push_back(&-1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        let c = array_obj.push_front(&3).expect("push error");
+        array_obj.remove_at(b).expect("remove error");
+        let handle = array_obj.get_handle(c).expect("handle error");
+
+        let bytes = array_obj.to_bytes();
+        assert_eq!(&bytes[..4], b"QARR");
+        assert_eq!(bytes.len(), 32 + 5 * (13 + 8));
+
+        let restored = QuickArray::<i64, u16>::from_bytes(&bytes).expect("load error");
+        assert_eq!(restored, array_obj);
+        assert_eq!(restored.get_by_handle(handle), Some(&3));
+        assert_eq!(restored.free_indices().collect::<Vec<u32>>(), array_obj.free_indices().collect::<Vec<u32>>());
+        assert_eq!(restored.to_bytes(), bytes);
+        restored.check_integrity().expect("integrity error");
+
+        assert!(matches!(QuickArray::<i64, u16>::from_bytes(&bytes[..bytes.len() - 1]), Err(ErrDefine::Corrupted)));
+        assert!(matches!(QuickArray::<i32, u16>::from_bytes(&bytes), Err(ErrDefine::Corrupted)));
+
+        let mut broken = bytes.clone();
+        broken[4] = 2;
+        assert!(matches!(QuickArray::<i64, u16>::from_bytes(&broken), Err(ErrDefine::Corrupted)));
+
+        let mut broken = bytes.clone();
+        broken[16..20].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(QuickArray::<i64, u16>::from_bytes(&broken), Err(ErrDefine::Corrupted)));
+
+        // a huge size in the header is rejected instead of overflowing the length check
+        let mut broken = QuickArray::<u128>::new(2).to_bytes();
+        broken[8..12].copy_from_slice(&(u32::MAX - 1).to_le_bytes());
+        assert!(matches!(QuickArray::<u128>::from_bytes(&broken), Err(ErrDefine::Corrupted)));
+
+        let empty = QuickArray::<f32>::new(2);
+        let restored = QuickArray::<f32>::from_bytes(&empty.to_bytes()).expect("load error");
+        assert!(restored.is_empty());
+        restored.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_fixed_array() {
+        let mut array_obj = QuickArrayFixed::<u32, 4>::default();
+        assert_eq!(array_obj.capacity(), 4);
+        assert!(array_obj.is_empty());
+
+        let first = array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        array_obj.push_front(&0).expect("push error");
+        array_obj.push_back(&3).expect("push error");
+        assert!(matches!(array_obj.push_back(&4), Err(ErrDefine::ArrayIsFull)));
+        assert_eq!(array_obj.to_vec(), vec![0, 1, 2, 3]);
+
+        assert_eq!(array_obj.remove_at(first).expect("remove error"), 1);
+        let head = array_obj.get_head_index().expect("head error");
+        array_obj.insert_after(head, &9).expect("insert error");
+        array_obj.check_integrity().expect("integrity error");
+
+        let copy = array_obj.clone();
+        assert_eq!(copy, array_obj);
+        assert_eq!(copy.to_string(), "[0, 9, 2, 3]");
+        assert_eq!(array_obj.into_iter().rev().collect::<Vec<_>>(), vec![3, 2, 9, 0]);
+    }
+
+    #[test]
+    fn test_with_storage() {
+        let mut array_obj = QuickArray::<u8, u16>::new(3);
+        array_obj.push_back(&7).expect("push error");
+
+        // reusing an old buffer discards its contents
+        let mut reused = QuickArray::<u8, u16>::with_storage(array_obj.internal_vec);
+        assert!(reused.is_empty());
+        assert_eq!(reused.capacity(), 3);
+        reused.push_front(&1).expect("push error");
+        reused.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_slice_storage() {
+        let mut buffer = [MaybeUninit::uninit(); 4];
+        let mut array_obj = QuickArraySlice::<u64, u16>::from_slice_storage(&mut buffer);
+        assert_eq!(array_obj.capacity(), 4);
+
+        let index = array_obj.push_back(&10).expect("push error");
+        array_obj.push_back(&20).expect("push error");
+        array_obj.insert_before(index, &5).expect("insert error");
+        array_obj.check_integrity().expect("integrity error");
+        assert_eq!(array_obj.to_vec(), vec![5, 10, 20]);
+
+        array_obj.remove_at(index).expect("remove error");
+        array_obj.push_front(&1).expect("push error");
+        array_obj.push_front(&0).expect("push error");
+        assert!(array_obj.is_full());
+        assert_eq!(array_obj.to_vec(), vec![0, 1, 5, 20]);
+    }
+
+    #[test]
+    fn test_boxed_storage() {
+        let slots = vec![QuickElement::default(); 3].into_boxed_slice();
+        let mut array_obj = QuickArray::<u32, u32, Box<[_]>>::with_storage(slots);
+        array_obj.push_back(&1).expect("push error");
+        array_obj.push_back(&2).expect("push error");
+        array_obj.push_front(&0).expect("push error");
+        assert!(matches!(array_obj.push_back(&3), Err(ErrDefine::ArrayIsFull)));
+        array_obj.check_integrity().expect("integrity error");
+
+        let copy = array_obj.clone();
+        assert_eq!(Vec::from(copy), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_u8_index() {
+        let max_size = u8::MAX as u32 - 1;
+        let mut array_obj = QuickArray::<u8, u8>::new(max_size);
+        for i in 0..max_size {
+            array_obj.push_front(&(i as u8)).expect("push error");
+        }
+        assert!(matches!(array_obj.push_back(&0), Err(ErrDefine::ArrayIsFull)));
+        assert_eq!(array_obj.get_head_index(), Some(max_size - 1));
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.remove_range(0, 10).expect("remove error");
+        array_obj.reserve(0);
+        assert!(matches!(array_obj.expand_to(u8::MAX as u32), Err(ErrDefine::ArraySizeError)));
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+
+    #[test]
+    fn test_move_to_front_and_truncate() {
+        let mut array_obj = QuickArray::<i32>::new(4);
+        assert!(matches!(array_obj.move_to_front(0), Err(ErrDefine::InvalidIndex)));
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.push_back(&2).expect("push error");
+        let c = array_obj.push_back(&3).expect("push error");
+        let handle = array_obj.get_handle(c).expect("handle error");
+
+        array_obj.move_to_front(a).expect("move error");
+        assert_eq!(array_obj.to_vec(), vec![1, 2, 3]);
+        array_obj.move_to_front(b).expect("move error");
+        assert_eq!(array_obj.to_vec(), vec![2, 1, 3]);
+        array_obj.move_to_front(c).expect("move error");
+        assert_eq!(array_obj.to_vec(), vec![3, 2, 1]);
+        assert_eq!(array_obj.get_tail_index(), Some(a));
+        assert_eq!(array_obj.get_by_handle(handle), Some(&3));
+        array_obj.check_integrity().expect("integrity error");
+
+        array_obj.truncate(5);
+        assert_eq!(array_obj.len(), 3);
+        array_obj.truncate(1);
+        assert_eq!(array_obj.to_vec(), vec![3]);
+        array_obj.truncate(0);
+        assert!(array_obj.is_empty());
+        array_obj.check_integrity().expect("integrity error");
+    }
+
+    #[test]
+    fn test_evicting_array_mutators() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let evicted = Rc::new(RefCell::new(Vec::<i32>::new()));
+        let mut array_obj = EvictingQuickArray::<i32>::new(8);
+        let sink = Rc::clone(&evicted);
+        array_obj.on_evict(move |_, data| sink.borrow_mut().push(*data));
+        let take = || mem::take(&mut *(*evicted).borrow_mut());
+
+        // non-removing mutators never report
+        let a = array_obj.push_back(&1).expect("push error");
+        let b = array_obj.insert_after(a, &3).expect("insert error");
+        array_obj.insert_before(b, &2).expect("insert error");
+        array_obj.update_at(a, &10).expect("update error");
+        array_obj.update_with(b, |data| *data += 20).expect("update error");
+        *array_obj.get_element_mut(a).expect("get error") += 1;
+        array_obj.move_to_front(b).expect("move error");
+        assert_eq!(array_obj.to_vec(), vec![23, 11, 2]);
+        array_obj.iter_mut().for_each(|(_, data)| *data -= 1);
+        array_obj.sort_by(|x, y| x.cmp(y));
+        assert_eq!(array_obj.to_vec(), vec![1, 10, 22]);
+        assert!(take().is_empty());
+
+        // every removing method reports each removed value
+        assert_eq!(array_obj.pop_front(), Some(1));
+        assert_eq!(array_obj.pop_back(), Some(22));
+        assert_eq!(take(), vec![1, 22]);
+
+        for i in 0..6 {
+            array_obj.push_back(&i).expect("push error");
         }
+        let index = array_obj.find(|data| *data == 2).expect("find error");
+        assert_eq!(array_obj.swap_remove(index).expect("remove error"), 2);
+        assert_eq!(array_obj.get_element(index), Some(&5));
+        assert!(array_obj.remove_value(&4).is_some());
+        assert_eq!(take(), vec![2, 4]);
+
+        let indices = array_obj.index_snapshot();
+        assert_eq!(array_obj.remove_many(&[indices[0], indices[0], 99]), 1);
+        assert_eq!(take(), vec![10]);
+
+        array_obj.retain(|data| data % 2 == 1);
+        assert_eq!(array_obj.to_vec(), vec![1, 5, 3]);
+        assert_eq!(take(), vec![0]);
+
+        array_obj.push_front(&-1).expect("push error");
+        assert_eq!(array_obj.drain_while(|data| *data < 0), vec![-1]);
+        assert_eq!(take(), vec![-1]);
+
+        array_obj.push_back(&7).expect("push error");
+        array_obj.truncate(1);
+        assert_eq!(take(), vec![7, 3, 5]);
+        array_obj.clear();
+        assert_eq!(take(), vec![1]);
+        assert!(array_obj.is_empty());
+        array_obj.check_integrity().expect("integrity error");
     }
 }